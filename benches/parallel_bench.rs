@@ -0,0 +1,82 @@
+use bevy_wave_collapse::{
+    objects::hexs_map::{HexId, HexTrig},
+    prelude::*,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use fixed::{types::extra::U16, FixedI32};
+
+type P = FixedI32<U16>;
+
+/// The river core with a straight and a corner turned to each of the six sides
+fn load_cell() -> Vec<WaveMesh<P, u8>> {
+    let meshes = WaveMesh::<P, u8>::from_obj_str(include_str!("river.wfo")).unwrap();
+    let mut cell = vec![meshes["CORE"].clone()];
+    for i in 0..6 {
+        for name in [if i % 2 == 0 { "SW" } else { "SF" }, "CWF"] {
+            let mut mesh = meshes[name].clone();
+            mesh.rotate(P::ROTATIONS_SIN[i], P::ROTATIONS_COS[i]);
+            cell.push(mesh);
+        }
+    }
+    cell
+}
+
+fn bake_cell(
+    builder: &mut WaveBuilder<P, u8>,
+    cell: &[WaveMesh<P, u8>],
+    id: &HexId,
+) -> Result<(), BakeError> {
+    for mesh in cell {
+        builder.bake(id.world_offset(), mesh)?;
+    }
+    Ok(())
+}
+
+fn parallel_test(c: &mut Criterion) {
+    let cell = load_cell();
+    let ids: Vec<HexId> = HexId::spiral(HexId::ZERO, 40).collect();
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+
+    let mut sequential = WaveBuilder::new();
+    for id in ids.iter() {
+        bake_cell(&mut sequential, &cell, id).unwrap();
+    }
+    let parallel =
+        WaveBuilder::bake_parallel(&ids, threads, |builder, id| bake_cell(builder, &cell, id))
+            .unwrap();
+    assert_eq!(sequential.extract(), parallel.extract());
+    println!(
+        "{} hexs, {} vertexes, {} threads",
+        ids.len(),
+        parallel.vertex_len(),
+        threads
+    );
+
+    let mut group = c.benchmark_group("Parallel Bake");
+    group.sample_size(20);
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            let mut builder = WaveBuilder::new();
+            for id in ids.iter() {
+                bake_cell(&mut builder, &cell, id).unwrap();
+            }
+            builder.vertex_len()
+        });
+    });
+    let mut counts = vec![1, 2, 4, threads];
+    counts.sort();
+    counts.dedup();
+    for count in counts {
+        group.bench_with_input(BenchmarkId::new("parallel", count), &count, |b, &count| {
+            b.iter(|| {
+                WaveBuilder::bake_parallel(&ids, count, |builder, id| bake_cell(builder, &cell, id))
+                    .unwrap()
+                    .vertex_len()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parallel_test);
+criterion_main!(benches);
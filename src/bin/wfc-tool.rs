@@ -0,0 +1,324 @@
+//! Convert, inspect and validate tileset files from the command line
+//!
+//! ```text
+//! wfc-tool list <file>...
+//! wfc-tool convert <input> <output.wfo|output.wfb>
+//! wfc-tool validate <file> [--object river|desert] [--tolerance <distance>]
+//! ```
+//! `.obj` and `.wfo` files are read as text with any `.mtl` files they use, `.wfb` files as binary.
+//! Any command takes `--material <name>=<uv>` to give a material name its UV,
+//! materials with a number for a name are that palette cell and other names get a free cell picked by their name.
+//! Positions are `FixedI32<U16>` and UVs are `u8` palette cells, the same as the example objects
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bevy_wave_collapse::{
+    objects::hexs_map::{desert::Desert, river::RiverObject},
+    prelude::*,
+};
+use fixed::{types::extra::U16, FixedI32};
+
+type P = FixedI32<U16>;
+#[cfg(feature = "bevy")]
+type Meshes = bevy::utils::HashMap<String, WaveMesh<P, u8>>;
+#[cfg(not(feature = "bevy"))]
+type Meshes = HashMap<String, WaveMesh<P, u8>>;
+
+const USAGE: &str = "usage:
+    wfc-tool list <file>...
+    wfc-tool convert <input> <output.wfo|output.wfb>
+    wfc-tool validate <file> [--object river|desert] [--tolerance <distance>]
+options:
+    --material <name>=<uv>    use palette cell <uv> for material <name>";
+
+fn main() -> ExitCode {
+    let mut args = Vec::new();
+    let mut palette = MaterialPalette::new();
+    let mut input = std::env::args().skip(1);
+    while let Some(arg) = input.next() {
+        if arg != "--material" {
+            args.push(arg);
+            continue;
+        }
+        let material = input.next().and_then(|material| {
+            let (name, uv) = material.split_once('=')?;
+            Some((name.to_string(), uv.parse().ok()?))
+        });
+        let Some((name, uv)) = material else {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        };
+        palette = palette.with_name(name, uv);
+    }
+    let result = match args.first().map(String::as_str) {
+        Some("list") if args.len() > 1 => list(&args[1..], &palette),
+        Some("convert") if args.len() == 3 => {
+            convert(Path::new(&args[1]), Path::new(&args[2]), &palette)
+        }
+        Some("validate") if args.len() > 1 => validate(&args[1..], &palette),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn is_binary(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "wfb")
+}
+
+/// Read a mesh file, errors point at the file and line they came from
+fn load(path: &Path, palette: &MaterialPalette<u8>) -> Result<Meshes, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if is_binary(path) {
+        return WaveMesh::from_binary(&bytes).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let folder = path.parent().unwrap_or(Path::new(""));
+    let mut palette = palette.clone();
+    for library in mtl_libraries(&text) {
+        let library = folder.join(library);
+        // a missing library only matters if a material needs it, which fails with UnknownMaterial
+        let Ok(mtl) = std::fs::read_to_string(&library) else {
+            continue;
+        };
+        palette
+            .load_mtl(&mtl)
+            .map_err(|e| diagnostic(&library, &mtl, &e))?;
+    }
+    WaveMesh::from_obj_str_with_palette(&text, &palette).map_err(|e| diagnostic(path, &text, &e))
+}
+
+/// `file:line: error` followed by the line itself
+fn diagnostic(path: &Path, text: &str, error: &ParseObjError) -> String {
+    match error.line() {
+        Some(line) => format!(
+            "{}:{}: {}\n    {}",
+            path.display(),
+            line + 1,
+            error,
+            text.lines().nth(line).unwrap_or_default().trim()
+        ),
+        None => format!("{}: {}", path.display(), error),
+    }
+}
+
+fn sorted(meshes: &Meshes) -> Vec<(&String, &WaveMesh<P, u8>)> {
+    let mut meshes: Vec<_> = meshes.iter().collect();
+    meshes.sort_by(|a, b| a.0.cmp(b.0));
+    meshes
+}
+
+fn list(paths: &[String], palette: &MaterialPalette<u8>) -> Result<bool, String> {
+    for path in paths {
+        let meshes = load(Path::new(path), palette)?;
+        println!("{}: {} meshes", path, meshes.len());
+        for (name, mesh) in sorted(&meshes) {
+            println!(
+                "    {:<24} {:>6} vertices {:>6} triangles",
+                name,
+                mesh.vertexs.len(),
+                mesh.indices.len() / 3
+            );
+        }
+    }
+    Ok(true)
+}
+
+fn convert(input: &Path, output: &Path, palette: &MaterialPalette<u8>) -> Result<bool, String> {
+    let meshes = load(input, palette)?;
+    let data = if is_binary(output) {
+        WaveMesh::to_binary(&meshes)
+    } else {
+        WaveMesh::to_obj_file(&meshes, None).into_bytes()
+    };
+    std::fs::write(output, data).map_err(|e| format!("{}: {}", output.display(), e))?;
+    println!(
+        "wrote {} meshes from {} to {}",
+        meshes.len(),
+        input.display(),
+        output.display()
+    );
+    Ok(true)
+}
+
+fn validate(args: &[String], palette: &MaterialPalette<u8>) -> Result<bool, String> {
+    let mut path = None;
+    let mut object = None;
+    let mut tolerance = 0.001;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--object" => object = Some(args.next().ok_or(USAGE)?.clone()),
+            "--tolerance" => {
+                tolerance = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or(USAGE)?
+            }
+            _ if path.is_none() => path = Some(PathBuf::from(arg)),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let path = path.ok_or(USAGE)?;
+    let meshes = load(&path, palette)?;
+    let mut ok = true;
+
+    if let Some(object) = object {
+        let has = |name: &str| meshes.contains_key(name);
+        let missing = match object.as_str() {
+            "river" => RiverObject::missing_meshes(has),
+            "desert" => Desert::missing_meshes(has),
+            object => {
+                return Err(format!(
+                    "unknown object {}, expected river or desert",
+                    object
+                ))
+            }
+        };
+        for name in missing.iter() {
+            println!(
+                "{}: missing mesh {} needed by {}",
+                path.display(),
+                name,
+                object
+            );
+        }
+        ok &= missing.is_empty();
+    }
+
+    for (name, mesh) in sorted(&meshes) {
+        for triangle in degenerate_triangles(mesh) {
+            println!(
+                "{}: {}: triangle {} has no area",
+                path.display(),
+                name,
+                triangle
+            );
+            ok = false;
+        }
+        for (a, b) in unmerged_vertices(mesh, tolerance) {
+            println!(
+                "{}: {}: vertices {} and {} are within {} of each other but will not be merged",
+                path.display(),
+                name,
+                a,
+                b,
+                tolerance
+            );
+            ok = false;
+        }
+    }
+    if ok {
+        println!("{}: ok", path.display());
+    }
+    Ok(ok)
+}
+
+/// Triangles that use a vertex twice or whose corners lie on a line
+fn degenerate_triangles(mesh: &WaveMesh<P, u8>) -> Vec<usize> {
+    let positions: Vec<[f32; 3]> = mesh
+        .vertexs
+        .iter()
+        .map(|vertex| vertex.position.to_f32x3())
+        .collect();
+    mesh.indices
+        .chunks_exact(3)
+        .enumerate()
+        .filter(|(_, triangle)| {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+            let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let cross = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            let squared = |v: [f32; 3]| v.iter().map(|v| v * v).sum::<f32>();
+            // compared with the edges so small and large tiles are judged alike
+            triangle[0] == triangle[1]
+                || triangle[1] == triangle[2]
+                || triangle[0] == triangle[2]
+                || squared(cross) <= f32::EPSILON * squared(ab) * squared(ac)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Pairs of vertices with the same UV that are closer than `tolerance` without being the same position,
+/// so `WaveBuilder::bake` keeps both and leaves a crack between them
+fn unmerged_vertices(mesh: &WaveMesh<P, u8>, tolerance: f32) -> Vec<(usize, usize)> {
+    let mut cells: HashMap<([i64; 3], u8), Vec<usize>> = HashMap::new();
+    for (index, vertex) in mesh.vertexs.iter().enumerate() {
+        let cell = vertex
+            .position
+            .to_f32x3()
+            .map(|axis| (axis / tolerance).floor() as i64);
+        cells.entry((cell, vertex.uv)).or_default().push(index);
+    }
+    let mut pairs = Vec::new();
+    for (index, vertex) in mesh.vertexs.iter().enumerate() {
+        let position = vertex.position.to_f32x3();
+        let cell = position.map(|axis| (axis / tolerance).floor() as i64);
+        // a close vertex is at most one cell away on each axis
+        for offset in 0..27 {
+            let neighbour = [
+                cell[0] + offset % 3 - 1,
+                cell[1] + offset / 3 % 3 - 1,
+                cell[2] + offset / 9 - 1,
+            ];
+            for other in cells.get(&(neighbour, vertex.uv)).into_iter().flatten() {
+                let other_position = mesh.vertexs[*other].position.to_f32x3();
+                let distance = (0..3)
+                    .map(|axis| (position[axis] - other_position[axis]).powi(2))
+                    .sum::<f32>()
+                    .sqrt();
+                if *other > index
+                    && distance < tolerance
+                    && mesh.vertexs[*other].position != vertex.position
+                {
+                    pairs.push((index, *other));
+                }
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_wave_collapse::vertex::Vertex;
+
+    fn triangle(corners: [[f32; 3]; 3]) -> WaveMesh<P, u8> {
+        let mut mesh = WaveMesh::new();
+        for corner in corners {
+            let [x, y, z] = corner.map(P::from_num);
+            mesh.vertexs.push(Vertex::new(RVec3::new(x, y, z), 0));
+        }
+        mesh.indices = vec![0, 1, 2];
+        mesh
+    }
+
+    #[test]
+    fn small_triangles_are_not_degenerate() {
+        let small = triangle([[0., 0., 0.], [0.002, 0., 0.], [0., 0., 0.002]]);
+        assert!(degenerate_triangles(&small).is_empty());
+    }
+
+    #[test]
+    fn long_slivers_are_degenerate() {
+        let sliver = triangle([[0., 0., 0.], [100., 0., 0.], [50., 0., 0.]]);
+        assert_eq!(degenerate_triangles(&sliver), vec![0]);
+    }
+}
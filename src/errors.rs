@@ -4,7 +4,7 @@ use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum BakeError {
-    #[error("Mesh {mesh} for {obj} not found in Assets")]
+    #[error("Mesh {mesh} for {obj} not found in mesh storage")]
     MeshNotFound{mesh: &'static str, obj: &'static str },
     #[error("Mesh {mesh} not set for {obj}")]
     MeshNotSet{mesh: &'static str, obj: &'static str },
@@ -26,4 +26,114 @@ pub enum ParseObjError {
     FailedToParseFloat(core::num::ParseFloatError, usize),
     #[error("Failed to Parse {0} on line {1}")]
     FailedToParse(&'static str, usize),
+    #[error("Index {index} on line {line} does not point at anything read so far")]
+    IndexOutOfRange { index: isize, line: usize },
+    #[error("Material {0} on line {1} is not in the palette")]
+    UnknownMaterial(String, usize),
+}
+
+impl ParseObjError {
+    /// The line the error was found on, counting from 0
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParseObjError::NoMeshs => None,
+            ParseObjError::NoName(line)
+            | ParseObjError::UnknownSymbol(_, line)
+            | ParseObjError::ExpectedSymbol { line, .. }
+            | ParseObjError::FailedToParseInt(_, line)
+            | ParseObjError::FailedToParseFloat(_, line)
+            | ParseObjError::FailedToParse(_, line)
+            | ParseObjError::IndexOutOfRange { line, .. }
+            | ParseObjError::UnknownMaterial(_, line) => Some(*line),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CollapseError {
+    #[error("Cell {cell} has no tiles left that fit its neighbours")]
+    Contradiction { cell: usize },
+    #[error("Tile {0} is not part of the rules")]
+    UnknownTile(String),
+    #[error("Cell {0} is not part of the graph")]
+    UnknownCell(usize),
+    #[error("Tile {tile} has {found} sockets but the graph has {expected} sides")]
+    MissingSockets {
+        tile: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Tile {0} is given by more than one object")]
+    DuplicateTile(String),
+}
+
+#[derive(Debug, Error)]
+pub enum RecoveryError {
+    #[error("Gave up at cell {cell} after {restarts} restarts, {backtracks} backtracks and {cells_resolved} cells re-solved")]
+    BudgetExhausted {
+        cell: usize,
+        restarts: usize,
+        backtracks: usize,
+        cells_resolved: usize,
+    },
+    #[error(transparent)]
+    Collapse(#[from] CollapseError),
+}
+
+#[derive(Debug, Error)]
+pub enum LearnError {
+    #[error("The pattern window does not contain the origin so patterns have no anchor cell")]
+    NoAnchor,
+    #[error("No pattern window fits completely inside the example")]
+    NoPatterns,
+}
+
+#[derive(Debug, Error)]
+pub enum ParseModelError {
+    #[error("The data ended early, expected {0}")]
+    UnexpectedEnd(&'static str),
+    #[error("Expected {expect} on line {line}")]
+    ExpectedSymbol { expect: &'static str, line: usize },
+    #[error("Failed to Parse Int on line {1}")]
+    FailedToParseInt(core::num::ParseIntError, usize),
+    #[error("The model has {found} directions but the graph has {expected}")]
+    WrongDirections { expected: usize, found: usize },
+    #[error("Index {index} on line {line} is out of range")]
+    OutOfRange { index: usize, line: usize },
+}
+
+#[derive(Debug, Error)]
+pub enum ParseBinaryError {
+    #[error("The data is not a binary wave mesh file")]
+    NotBinaryMesh,
+    #[error("Version {0} binary meshes are not supported")]
+    UnsupportedVersion(u16),
+    #[error("The data ended early, expected {0}")]
+    UnexpectedEnd(&'static str),
+    #[error("The checksum does not match, expected {expected:#010x} found {found:#010x}")]
+    ChecksumMismatch { expected: u32, found: u32 },
+    #[error("The {what} were saved as format {found:#06x} but are loaded as {expected:#06x}")]
+    WrongFormat {
+        what: &'static str,
+        expected: u16,
+        found: u16,
+    },
+    #[error("Mesh name is not valid UTF-8")]
+    InvalidName(#[from] std::string::FromUtf8Error),
+    #[error("Index {index} in mesh {mesh} is out of range")]
+    IndexOutOfRange { mesh: String, index: u32 },
+    #[error("Found {0} bytes after the last mesh")]
+    TrailingBytes(usize),
+}
+
+#[derive(Debug, Error)]
+pub enum GenerateError {
+    #[error("No objects are registered to build the map from")]
+    NoObjects,
+    #[error(transparent)]
+    Collapse(#[from] CollapseError),
+    #[error(transparent)]
+    Recovery(#[from] RecoveryError),
+    #[error(transparent)]
+    Bake(#[from] BakeError),
 }
@@ -4,17 +4,41 @@ mod errors;
 pub mod vertex;
 mod wave_mesh;
 
-#[cfg(feature = "with_bevy")]
-/// I cant be fucked making two copys of this one using bevy handles the other just with owned data
 pub mod objects;
 
+pub mod solver;
+
+#[cfg(feature = "with_bevy")]
+mod plugin;
+
 pub mod prelude {
-    pub use super::vector::RVec3;
-    pub use super::vertex::{VertexPosition, VertexUV};
+    pub use super::vector::{RQuat, RTransform, RVec3};
+    pub use super::vertex::{
+        Quantised, QuantisedF32, QuantisedF64, RawBits, VertexPosition, VertexUV,
+    };
     #[cfg(feature = "with_bevy")]
-    pub use super::wave_mesh::loader::WaveMeshObjLoader;
+    pub use super::wave_mesh::loader::{WaveMeshBinaryLoader, WaveMeshObjLoader};
     pub use super::wave_mesh::WaveBuilder;
+    pub use super::wave_mesh::{
+        material_names, mtl_libraries, EdgePlane, EdgeProfile, MaterialPalette, MeshWithNormals,
+        NormalMode,
+    };
     pub use super::wave_mesh::WaveMesh;
-    pub use super::errors::{BakeError, ParseObjError};
-    pub use super::objects::Connection;
+    pub use super::errors::{
+        BakeError, CollapseError, GenerateError, LearnError, ParseBinaryError, ParseModelError, ParseObjError,
+        RecoveryError,
+    };
+    #[cfg(feature = "with_bevy")]
+    pub use super::plugin::{
+        ChunkFailed, ChunkFocus, ChunkStreaming, GenerateMap, GenerateMapAsync, MapChunk,
+        MapFailed, MapMeshes, MapObject, MapTask, MapTaskFailed, WaveCollapsePlugin, WaveMap,
+        WaveObjectRegistry,
+    };
+    pub use super::objects::{Connection, MeshStorage, OwnedMeshes};
+    #[cfg(feature = "with_bevy")]
+    pub use super::objects::{AssetMeshes, MeshSnapshot};
+    pub use super::solver::{
+        AdjacencyRules, CellGraph, CollapseStats, Collapsed, OverlappingModel, Recovery,
+        WaveSolver,
+    };
 }
@@ -0,0 +1,234 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use super::{HexId, HexTrig};
+use crate::{
+    errors::{BakeError, CollapseError, RecoveryError},
+    objects::{MeshStorage, SeededWaveObjects, WaveObject},
+    solver::{AdjacencyRules, CellGraph, CollapseStats, Collapsed, Recovery, WaveSolver},
+    vertex::{VertexPosition, VertexUV},
+    wave_mesh::WaveBuilder,
+};
+
+/// A hex map object that sees its neighbours as [`SeededWaveObjects`], meshes kept in `S`
+pub type HexObject<P, UV, S> = WaveObject<P, UV, SeededWaveObjects<P, UV, S, 6>, S>;
+
+/// A chunk of a [`ChunkedMap`], chunk `(q, r)` holds the `size` by `size` cells starting at `(q * size, r * size)`.
+/// Chunks neighbour each other the same way cells do so `HexId` does the work
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct ChunkId(pub HexId);
+
+impl ChunkId {
+    /// The chunk `cell` is in
+    pub fn of(cell: HexId, size: u32) -> ChunkId {
+        let size = size as i32;
+        ChunkId(HexId::new(cell.q.div_euclid(size), cell.r.div_euclid(size)))
+    }
+
+    /// The first cell of the chunk, chunks are baked around this cell
+    pub fn origin(&self, size: u32) -> HexId {
+        self.0 * size as i32
+    }
+
+    /// Every cell in the chunk
+    pub fn cells(&self, size: u32) -> impl Iterator<Item = HexId> {
+        let origin = self.origin(size);
+        (0..size as i32).flat_map(move |q| (0..size as i32).map(move |r| origin + HexId::new(q, r)))
+    }
+
+    pub fn neighbours(&self) -> [ChunkId; 6] {
+        self.0.neighbours().map(ChunkId)
+    }
+}
+
+/// Mix the map seed with a cell so every cell rolls its own details
+pub(crate) fn cell_seed(seed: u64, cell: HexId) -> u64 {
+    seed ^ ((cell.q as u32 as u64) << 32 | cell.r as u32 as u64)
+}
+
+/// A hex map too big for one [`WaveBuilder`], solved and baked a chunk at a time.
+/// Cells next to chunks that are already solved are held to the tiles found there so chunks meet without seams.
+/// Solved tiles are remembered so a chunk baked again, such as when it comes back into view, looks the same
+#[derive(Debug, Clone)]
+pub struct ChunkedMap {
+    /// The number of cells along each side of a chunk
+    pub size: u32,
+    pub seed: u64,
+    /// How the solver gets out of contradictions within a chunk
+    pub recovery: Recovery,
+    tiles: HashMap<HexId, usize>,
+    solved: HashSet<ChunkId>,
+}
+
+impl ChunkedMap {
+    pub fn new(size: u32, seed: u64) -> Self {
+        ChunkedMap {
+            size: size.max(1),
+            seed,
+            recovery: Recovery::default(),
+            tiles: HashMap::new(),
+            solved: HashSet::new(),
+        }
+    }
+
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// The chunk `cell` is in
+    pub fn chunk(&self, cell: HexId) -> ChunkId {
+        ChunkId::of(cell, self.size)
+    }
+
+    pub fn is_solved(&self, chunk: ChunkId) -> bool {
+        self.solved.contains(&chunk)
+    }
+
+    /// The tile index picked for `cell`, `None` until its chunk is solved
+    pub fn tile(&self, cell: HexId) -> Option<usize> {
+        self.tiles.get(&cell).copied()
+    }
+
+    /// Solve every cell of `chunk` with `rules`, cells in neighbouring chunks that are already solved
+    /// are part of the graph with their tiles set so the chunk fits against them.
+    /// When the chunk can not be solved against them the border cell nearest the contradiction is let go
+    /// and the chunk is solved again, so a chunk boxed in by its neighbours is still solved with a seam there
+    pub fn solve(
+        &mut self,
+        chunk: ChunkId,
+        rules: &AdjacencyRules<6>,
+    ) -> Result<CollapseStats, RecoveryError> {
+        let mut cells: Vec<HexId> = chunk.cells(self.size).collect();
+        let inner = cells.len();
+        for index in 0..inner {
+            for neighbour in cells[index].neighbours() {
+                if self.chunk(neighbour) != chunk
+                    && self.tiles.contains_key(&neighbour)
+                    && !cells[inner..].contains(&neighbour)
+                {
+                    cells.push(neighbour);
+                }
+            }
+        }
+        let graph = HexId::graph(&cells);
+        let mut border: Vec<usize> = (inner..cells.len()).collect();
+        let (collapsed, stats) = loop {
+            let error = match self.solve_against(chunk, rules, &graph, &cells, &border) {
+                Ok(solved) => break solved,
+                Err(error) => error,
+            };
+            let cell = match &error {
+                RecoveryError::Collapse(CollapseError::Contradiction { cell })
+                | RecoveryError::BudgetExhausted { cell, .. } => cells[*cell],
+                _ => return Err(error),
+            };
+            let Some(nearest) =
+                (0..border.len()).min_by_key(|index| cells[border[*index]].distance(cell))
+            else {
+                return Err(error);
+            };
+            border.swap_remove(nearest);
+        };
+        for (index, cell) in cells.iter().enumerate().take(inner) {
+            self.tiles.insert(*cell, collapsed.tile(index));
+        }
+        self.solved.insert(chunk);
+        Ok(stats)
+    }
+
+    /// Solve the chunk in `graph` with the `border` cells held to their tiles
+    fn solve_against(
+        &self,
+        chunk: ChunkId,
+        rules: &AdjacencyRules<6>,
+        graph: &CellGraph<6>,
+        cells: &[HexId],
+        border: &[usize],
+    ) -> Result<(Collapsed, CollapseStats), RecoveryError> {
+        let mut solver = WaveSolver::new(rules, graph, cell_seed(self.seed, chunk.0));
+        for index in border {
+            solver.set(*index, rules.tile(self.tiles[&cells[*index]]))?;
+        }
+        solver.solve_with(self.recovery)
+    }
+
+    /// Bake a solved chunk around its [`ChunkId::origin`], `objects` is indexed by tile like the rules it was solved with.
+    /// Sides facing a chunk that is not solved yet see `edge` or the cell itself without one,
+    /// bake the chunk again once its neighbours are solved to join its edges up with them
+    pub fn bake<P: VertexPosition + HexTrig, UV: VertexUV, S: MeshStorage<P, UV> + ?Sized>(
+        &self,
+        chunk: ChunkId,
+        objects: &[Arc<HexObject<P, UV, S>>],
+        edge: Option<&Arc<HexObject<P, UV, S>>>,
+        meshes: &S,
+    ) -> Result<WaveBuilder<P, UV>, BakeError> {
+        let origin = chunk.origin(self.size);
+        let mut builder = WaveBuilder::new();
+        for cell in chunk.cells(self.size) {
+            let Some(object) = self.tile(cell).map(|tile| &objects[tile]) else {
+                continue;
+            };
+            let data = SeededWaveObjects {
+                neighbours: cell.neighbours().map(|neighbour| {
+                    self.tile(neighbour)
+                        .map_or(edge.unwrap_or(object), |tile| &objects[tile])
+                        .clone()
+                }),
+                seed: cell_seed(self.seed, cell),
+            };
+            object.build((cell - origin).world_offset(), meshes, &mut builder, &data)?;
+        }
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two tiles that only sit next to themselves, so a chunk is all one tile
+    fn islands() -> AdjacencyRules<6> {
+        let mut rules = AdjacencyRules::new(["A", "B"]);
+        for tile in ["A", "B"] {
+            rules.allow_all(&tile.into(), &tile.into()).unwrap();
+        }
+        rules
+    }
+
+    #[test]
+    fn chunks_fit_their_neighbours() {
+        let rules = islands();
+        let mut map = ChunkedMap::new(3, 7);
+        let first = ChunkId(HexId::new(0, 0));
+        let second = ChunkId(HexId::new(1, 0));
+        map.solve(first, &rules).unwrap();
+        map.solve(second, &rules).unwrap();
+        let tile = map.tile(first.origin(3)).unwrap();
+        for cell in first.cells(3).chain(second.cells(3)) {
+            assert_eq!(map.tile(cell), Some(tile));
+        }
+    }
+
+    #[test]
+    fn boxed_in_chunks_let_a_border_go() {
+        let rules = islands();
+        let mut map = ChunkedMap::new(3, 7);
+        let chunk = ChunkId(HexId::new(0, 0));
+        // neighbours on either side that can never both be matched
+        let [east, west] = [0, 3].map(|direction| chunk.neighbours()[direction]);
+        for (neighbour, tile) in [(east, 0), (west, 1)] {
+            for cell in neighbour.cells(3) {
+                map.tiles.insert(cell, tile);
+            }
+            map.solved.insert(neighbour);
+        }
+        map.solve(chunk, &rules).unwrap();
+        let tile = map.tile(chunk.origin(3)).unwrap();
+        for cell in chunk.cells(3) {
+            assert_eq!(map.tile(cell), Some(tile));
+        }
+    }
+}
@@ -1,66 +1,133 @@
 use super::*;
 use crate::{
     errors::BakeError,
-    objects::Connection,
+    objects::{mesh_key, Connection, MeshStorage, Socket, WaveTile},
     prelude::RVec3,
     vertex::{VertexPosition, VertexUV},
-    wave_mesh::{WaveBuilder, WaveMesh},
+    wave_mesh::WaveBuilder,
 };
-use bevy::asset::AssetPath;
 use fixed::{types::extra::LeEqU32, FixedI32};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use rand_distr::{Distribution, Pert, StandardGeometric};
 use strum::IntoEnumIterator;
 use strum_macros::{EnumIter, IntoStaticStr};
-pub struct Desert;
+/// Sand with cacti growing on it
+#[derive(Debug, Clone, Copy)]
+pub struct Desert {
+    /// The chance a tile grows cacti at all, between 0 and 1
+    pub cactus_chance: f64,
+}
+
+impl Default for Desert {
+    fn default() -> Self {
+        Desert { cactus_chance: 1. }
+    }
+}
+
+/// A desert object with its neighbours, meshes kept in `S`
+pub type DesertObject<P, UV, S> =
+    WaveObject<FixedI32<P>, UV, SeededWaveObjects<FixedI32<P>, UV, S, 6>, S>;
 
 use ConnectionType::*;
 impl Desert {
-    pub fn new<'a, P: LeEqU32 + Send + Sync, UV: VertexUV>(
-        asset_server: &AssetServer,
+    #[cfg(feature = "with_bevy")]
+    pub fn new<P: LeEqU32 + Send + Sync, UV: VertexUV>(
+        asset_server: &bevy::asset::AssetServer,
+        path: &str,
+    ) -> DesertObject<P, UV, AssetMeshes<FixedI32<P>, UV>>
+    where
+        FixedI32<P>: VertexPosition,
+    {
+        Desert::default().load(asset_server, path)
+    }
+
+    /// Load the meshes for a desert with these settings
+    #[cfg(feature = "with_bevy")]
+    pub fn load<P: LeEqU32 + Send + Sync, UV: VertexUV>(
+        self,
+        asset_server: &bevy::asset::AssetServer,
+        path: &str,
+    ) -> DesertObject<P, UV, AssetMeshes<FixedI32<P>, UV>>
+    where
+        FixedI32<P>: VertexPosition,
+    {
+        self.with_meshes(|label| {
+            asset_server.load(bevy::asset::AssetPath::new(path.into(), Some(label)))
+        })
+    }
+
+    /// A desert with these settings whose meshes were put in owned storage with
+    /// [`crate::objects::insert_meshes`] from the file at `path`
+    pub fn owned<P: LeEqU32 + Send + Sync, UV: VertexUV>(
+        self,
         path: &str,
-    ) -> WaveObject<FixedI32<P>, UV, SeededWaveObjects<'a, FixedI32<P>, UV, 6>>
+    ) -> DesertObject<P, UV, OwnedMeshes<FixedI32<P>, UV>>
+    where
+        FixedI32<P>: VertexPosition,
+    {
+        self.with_meshes(|label| mesh_key(path, &label))
+    }
+
+    /// A desert with the handle `load` gives for each mesh name
+    fn with_meshes<P: LeEqU32 + Send + Sync, UV: VertexUV, S: MeshStorage<FixedI32<P>, UV> + ?Sized>(
+        self,
+        mut load: impl FnMut(String) -> S::Handle,
+    ) -> DesertObject<P, UV, S>
     where
         FixedI32<P>: VertexPosition,
     {
         let mut meshes = HashMap::new();
         for connection in ConnectionType::iter() {
-            let path = AssetPath::new(path.into(), Some(format!("{:?}", connection)));
-            meshes.insert(Connection::from(connection), asset_server.load(path));
-        }
-        WaveObject {
-            meshes,
-            build_fn: Desert::bake,
-            can_connect_fn: Desert::can_connect,
+            meshes.insert(Connection::from(connection), load(format!("{:?}", connection)));
         }
+        WaveObject::new(meshes, self)
+    }
+
+    /// The meshes a desert file is missing according to `has`, which is given each mesh name
+    pub fn missing_meshes(has: impl Fn(&str) -> bool) -> Vec<String> {
+        ConnectionType::iter()
+            .map(<&'static str>::from)
+            .filter(|name| !has(name))
+            .map(str::to_string)
+            .collect()
     }
-    pub fn bake<'a, P: LeEqU32 + Send + Sync, UV: VertexUV>(
-        obj: &WaveObject<FixedI32<P>, UV, SeededWaveObjects<'a, FixedI32<P>, UV, 6>>,
+}
+
+impl<P: LeEqU32 + Send + Sync, UV: VertexUV, S: MeshStorage<FixedI32<P>, UV> + ?Sized>
+    WaveTile<FixedI32<P>, UV, SeededWaveObjects<FixedI32<P>, UV, S, 6>, S> for Desert
+where
+    FixedI32<P>: VertexPosition,
+{
+    fn build(
+        &self,
+        obj: &DesertObject<P, UV, S>,
         offset: RVec3<FixedI32<P>>,
-        meshs: &Assets<WaveMesh<FixedI32<P>, UV>>,
+        meshs: &S,
         main_mesh: &mut WaveBuilder<FixedI32<P>, UV>,
-        neighbours: &SeededWaveObjects<'a, FixedI32<P>, UV, 6>,
-    ) -> Result<(), BakeError>
-    where
-        FixedI32<P>: VertexPosition,
-    {
+        neighbours: &SeededWaveObjects<FixedI32<P>, UV, S, 6>,
+    ) -> Result<(), BakeError> {
         let mut rng = rand::rngs::StdRng::seed_from_u64(neighbours.seed);
         main_mesh.bake(
             offset,
             meshs
-                .get(
+                .mesh(
                     obj.get("Core")
                         .ok_or(BakeError::MeshNotSet{mesh: "Core",obj: "Sand"})?,
                 )
                 .ok_or(BakeError::MeshNotFound{mesh: "Core", obj: "Sand"})?,
         )?;
+        // skip the roll at full chance so it does not shift the cacti picked for a seed
+        if self.cactus_chance < 1. && !rng.gen_bool(self.cactus_chance.max(0.)) {
+            return Ok(());
+        }
         for (cactus, cactus_offset) in CactusShapes::ThreeDubble.gen_cactus(&mut rng) {
             cactus.build(offset + cactus_offset, meshs, main_mesh, &obj.meshes)?;
         }
         Ok(())
     }
-    pub fn can_connect(connection: Connection) -> bool {
-        connection == Connection::new("Sand")
+
+    fn sockets(&self) -> Vec<Socket> {
+        vec![Socket::new("Sand"); 6]
     }
 }
 
@@ -96,15 +163,15 @@ where
         }
     }
 
-    fn build<UV: VertexUV>(
+    fn build<UV: VertexUV, S: MeshStorage<FixedI32<P>, UV> + ?Sized>(
         self,
         offset: RVec3<FixedI32<P>>,
-        meshes: &Assets<WaveMesh<FixedI32<P>, UV>>,
+        meshes: &S,
         main_mesh: &mut WaveBuilder<FixedI32<P>, UV>,
-        wave_meshes: &HashMap<Connection, Handle<WaveMesh<FixedI32<P>, UV>>>,
+        wave_meshes: &HashMap<Connection, S::Handle>,
     ) -> Result<(), BakeError> {
         let stem = meshes
-            .get(
+            .mesh(
                 wave_meshes
                     .get(&Connection::from(if self.big {
                         CactusBig
@@ -121,7 +188,7 @@ where
         main_stem.scale_y(self.hight);
         main_mesh.bake(offset, &main_stem)?;
         let top = meshes
-            .get(
+            .mesh(
                 wave_meshes
                     .get(&Connection::from(CactusTop))
                     .ok_or(BakeError::MeshNotSet{mesh: "CactusTop", obj: "Desert"})?,
@@ -150,7 +217,7 @@ where
                 z: FixedI32::<P>::ZERO,
             });
             let mut branch = meshes
-                .get(
+                .mesh(
                     wave_meshes
                         .get(&Connection::from(CactusBranch))
                         .ok_or(BakeError::MeshNotSet{mesh: "CactusBranch", obj: "Desert"})?,
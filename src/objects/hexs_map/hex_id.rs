@@ -0,0 +1,252 @@
+use std::ops::{Add, Mul, Sub};
+
+use super::HexTrig;
+use crate::{
+    objects::GridTopology, solver::CellGraph, vector::RVec3, vertex::VertexPosition,
+    wave_mesh::EdgePlane,
+};
+
+/// Axial coordinate of a hex cell
+///
+/// Rows of constant `q` run along the x axis, `r` counts along a row.
+/// Neighbour direction `i` is the side a mesh faces once rotated by
+/// `HexTrig::ROTATIONS_SIN[i]`/`ROTATIONS_COS[i]`, so the same index can be used
+/// for `SeededWaveObjects::neighbours`, `CellGraph<6>` and mesh rotations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct HexId {
+    pub q: i32,
+    pub r: i32,
+}
+
+impl HexId {
+    pub const ZERO: HexId = HexId { q: 0, r: 0 };
+
+    /// The offset to the neighbour in each direction, in the same order as `HexTrig::ROTATIONS_SIN/COS`
+    pub const DIRECTIONS: [HexId; 6] = [
+        HexId { q: -1, r: 1 },
+        HexId { q: 0, r: 1 },
+        HexId { q: 1, r: 0 },
+        HexId { q: 1, r: -1 },
+        HexId { q: 0, r: -1 },
+        HexId { q: -1, r: 0 },
+    ];
+
+    pub const fn new(q: i32, r: i32) -> HexId {
+        HexId { q, r }
+    }
+
+    /// The third cube coordinate, `q + r + s` is always 0
+    pub const fn s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    pub const fn to_cube(&self) -> [i32; 3] {
+        [self.q, self.r, self.s()]
+    }
+
+    /// Returns None if the coordinates do not add up to 0
+    pub const fn from_cube([q, r, s]: [i32; 3]) -> Option<HexId> {
+        if q + r + s == 0 {
+            Some(HexId { q, r })
+        } else {
+            None
+        }
+    }
+
+    /// Convert to `(column, row)` offset coordinates where odd rows are shifted half a cell along +x
+    pub const fn to_offset(&self) -> (i32, i32) {
+        (self.r + (self.q - (self.q & 1)) / 2, self.q)
+    }
+
+    /// Convert from `(column, row)` offset coordinates where odd rows are shifted half a cell along +x
+    pub const fn from_offset(column: i32, row: i32) -> HexId {
+        HexId {
+            q: row,
+            r: column - (row - (row & 1)) / 2,
+        }
+    }
+
+    /// The neighbouring cell in `direction`, wraps around so any usize is valid
+    pub fn neighbour(&self, direction: usize) -> HexId {
+        *self + HexId::DIRECTIONS[direction % 6]
+    }
+
+    pub fn neighbours(&self) -> [HexId; 6] {
+        HexId::DIRECTIONS.map(|direction| *self + direction)
+    }
+
+    /// The number of steps between two cells
+    pub fn distance(&self, other: HexId) -> u32 {
+        let delta = *self - other;
+        (delta.q.unsigned_abs() + delta.r.unsigned_abs() + delta.s().unsigned_abs()) / 2
+    }
+
+    /// Rotate around the origin by `steps` directions, so `DIRECTIONS[i]` becomes `DIRECTIONS[i + steps]`
+    pub fn rotate(&self, steps: usize) -> HexId {
+        let mut hex = *self;
+        for _ in 0..steps % 6 {
+            hex = HexId {
+                q: hex.q + hex.r,
+                r: -hex.q,
+            };
+        }
+        hex
+    }
+
+    /// The position of the center of the cell for tiles modeled with a 0.5 corner radius,
+    /// neighbouring cells are sqrt(3)/2 apart
+    pub fn world_offset<P: VertexPosition + HexTrig>(&self) -> RVec3<P> {
+        RVec3::new(
+            P::from_f32(self.q as f32 * 0.5 + self.r as f32) * P::ROTATIONS_SIN[1],
+            P::default(),
+            P::from_f32(self.q as f32 * 0.75),
+        )
+    }
+
+    /// The cell whose center is closest to `x`, `z`, the reverse of [`HexId::world_offset`]
+    pub fn from_world(x: f32, z: f32) -> HexId {
+        let q = z / 0.75;
+        let r = x / (3f32.sqrt() / 2.) - q * 0.5;
+        let s = -q - r;
+        let (mut round_q, mut round_r, round_s) = (q.round(), r.round(), s.round());
+        // the coordinate that moved furthest when rounded is worked out from the other two
+        let (dq, dr, ds) = ((round_q - q).abs(), (round_r - r).abs(), (round_s - s).abs());
+        if dq > dr && dq > ds {
+            round_q = -round_r - round_s;
+        } else if dr > ds {
+            round_r = -round_q - round_s;
+        }
+        HexId::new(round_q as i32, round_r as i32)
+    }
+
+    /// Every cell exactly `radius` steps from `center`
+    pub fn ring(center: HexId, radius: u32) -> HexRing {
+        HexRing::new(center, radius)
+    }
+
+    /// Every cell within `radius` steps from `center`, starting at the center and working outwards ring by ring
+    pub fn spiral(center: HexId, radius: u32) -> HexSpiral {
+        HexSpiral {
+            ring: HexRing::new(center, 0),
+            center,
+            radius,
+        }
+    }
+
+    /// Build a graph from a list of cells, cell `i` in the graph is `cells[i]`
+    pub fn graph(cells: &[HexId]) -> CellGraph<6> {
+        CellGraph::from_cells(cells, HexId::neighbour)
+    }
+}
+
+impl<P: HexTrig + Copy> GridTopology<P, 6> for HexId {
+    const TURNS: usize = 6;
+    fn turn_side(side: usize, steps: usize) -> usize {
+        (side + steps) % 6
+    }
+    fn mirror_side(side: usize) -> usize {
+        // side 1 faces +x and side 4 faces -x
+        (11 - side % 6) % 6
+    }
+    fn turn(steps: usize) -> (P, P) {
+        (P::ROTATIONS_SIN[steps % 6], P::ROTATIONS_COS[steps % 6])
+    }
+    fn edge_plane(side: usize) -> EdgePlane {
+        // side 0 faces -60 degrees and neighbours are SIN60 apart so each side is half that from the center
+        let angle = ((side % 6) as f32 * 60. - 60.).to_radians();
+        EdgePlane::side(angle.cos(), angle.sin(), 0.866_025_4 / 2.)
+    }
+}
+
+impl Add for HexId {
+    type Output = HexId;
+    fn add(self, rhs: HexId) -> HexId {
+        HexId {
+            q: self.q + rhs.q,
+            r: self.r + rhs.r,
+        }
+    }
+}
+
+impl Sub for HexId {
+    type Output = HexId;
+    fn sub(self, rhs: HexId) -> HexId {
+        HexId {
+            q: self.q - rhs.q,
+            r: self.r - rhs.r,
+        }
+    }
+}
+
+impl Mul<i32> for HexId {
+    type Output = HexId;
+    fn mul(self, rhs: i32) -> HexId {
+        HexId {
+            q: self.q * rhs,
+            r: self.r * rhs,
+        }
+    }
+}
+
+/// Iterator over every cell exactly `radius` steps from a center cell
+pub struct HexRing {
+    current: HexId,
+    radius: u32,
+    side: usize,
+    step: u32,
+}
+
+impl HexRing {
+    fn new(center: HexId, radius: u32) -> HexRing {
+        HexRing {
+            current: center + HexId::DIRECTIONS[0] * radius as i32,
+            radius,
+            side: 0,
+            step: 0,
+        }
+    }
+}
+
+impl Iterator for HexRing {
+    type Item = HexId;
+    fn next(&mut self) -> Option<HexId> {
+        if self.side >= 6 {
+            return None;
+        }
+        let hex = self.current;
+        if self.radius == 0 {
+            self.side = 6;
+            return Some(hex);
+        }
+        // walking along DIRECTIONS[i + 2] goes from the corner at DIRECTIONS[i] to the one at DIRECTIONS[i + 1]
+        self.current = self.current.neighbour(self.side + 2);
+        self.step += 1;
+        if self.step == self.radius {
+            self.step = 0;
+            self.side += 1;
+        }
+        Some(hex)
+    }
+}
+
+/// Iterator over every cell within `radius` steps from a center cell, ring by ring
+pub struct HexSpiral {
+    ring: HexRing,
+    center: HexId,
+    radius: u32,
+}
+
+impl Iterator for HexSpiral {
+    type Item = HexId;
+    fn next(&mut self) -> Option<HexId> {
+        loop {
+            if let Some(hex) = self.ring.next() {
+                return Some(hex);
+            }
+            if self.ring.radius >= self.radius {
+                return None;
+            }
+            self.ring = HexRing::new(self.center, self.ring.radius + 1);
+        }
+    }
+}
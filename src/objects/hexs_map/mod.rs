@@ -1,6 +1,12 @@
+mod chunks;
 pub mod desert;
+mod hex_id;
 pub mod river;
 pub mod sand;
+#[cfg(feature = "with_bevy")]
+pub(crate) use chunks::cell_seed;
+pub use chunks::{ChunkId, ChunkedMap, HexObject};
+pub use hex_id::*;
 pub use trig::*;
 
 use super::*;
@@ -11,6 +17,8 @@ mod trig {
         FixedI32, FixedI64,
     };
 
+    use crate::vertex::Quantised;
+
     pub trait HexTrig: Sized {
         const ROTATIONS_COS: [Self; 6];
         const ROTATIONS_SIN: [Self; 6];
@@ -52,4 +60,53 @@ mod trig {
             FixedI64::<P>::lit("-0.86602540378"),
         ];
     }
+
+    impl HexTrig for f32 {
+        const ROTATIONS_COS: [f32; 6] = [1., 0.5, -0.5, -1., -0.5, 0.5];
+        const ROTATIONS_SIN: [f32; 6] = [
+            0.,
+            0.866_025_4,
+            0.866_025_4,
+            0.,
+            -0.866_025_4,
+            -0.866_025_4,
+        ];
+    }
+
+    impl HexTrig for f64 {
+        const ROTATIONS_COS: [f64; 6] = [1., 0.5, -0.5, -1., -0.5, 0.5];
+        const ROTATIONS_SIN: [f64; 6] = [
+            0.,
+            0.866_025_403_784_438_6,
+            0.866_025_403_784_438_6,
+            0.,
+            -0.866_025_403_784_438_6,
+            -0.866_025_403_784_438_6,
+        ];
+    }
+
+    impl<F: HexTrig + Copy, const STEPS: u32> HexTrig for Quantised<F, STEPS> {
+        const ROTATIONS_COS: [Quantised<F, STEPS>; 6] = {
+            let values = F::ROTATIONS_COS;
+            [
+                Quantised(values[0]),
+                Quantised(values[1]),
+                Quantised(values[2]),
+                Quantised(values[3]),
+                Quantised(values[4]),
+                Quantised(values[5]),
+            ]
+        };
+        const ROTATIONS_SIN: [Quantised<F, STEPS>; 6] = {
+            let values = F::ROTATIONS_SIN;
+            [
+                Quantised(values[0]),
+                Quantised(values[1]),
+                Quantised(values[2]),
+                Quantised(values[3]),
+                Quantised(values[4]),
+                Quantised(values[5]),
+            ]
+        };
+    }
 }
@@ -6,51 +6,96 @@ use crate::{
     vertex::{VertexPosition, VertexUV},
     wave_mesh::{WaveBuilder, WaveMesh},
 };
-use std::path::PathBuf;
 use strum::IntoEnumIterator;
 use strum_macros::{EnumIter, IntoStaticStr};
 
+#[derive(Debug, Clone, Copy, Default)]
 pub struct RiverObject;
 
-use bevy::asset::AssetPath;
+/// A river object with its neighbours, meshes kept in `S`
+pub type River<P, UV, S> =
+    WaveObject<FixedI32<P>, UV, SeededWaveObjects<FixedI32<P>, UV, S, 6>, S>;
+
 use fixed::{types::extra::LeEqU32, FixedI32};
 impl RiverObject {
-    pub fn new<'a, P: LeEqU32, UV: VertexUV>(
-        asset_server: &AssetServer,
+    /// A river whose meshes are loaded by the `AssetServer` from the file at `path`
+    #[cfg(feature = "with_bevy")]
+    pub fn new<P: LeEqU32, UV: VertexUV>(
+        asset_server: &bevy::asset::AssetServer,
         path: &str,
-    ) -> WaveObject<FixedI32<P>, UV, SeededWaveObjects<'a, FixedI32<P>, UV, 6>>
+    ) -> River<P, UV, AssetMeshes<FixedI32<P>, UV>>
     where
         FixedI32<P>: VertexPosition,
     {
-        let mut meshes = HashMap::new();
-        let base_path: PathBuf = path.into();
+        RiverObject::with_meshes(|label| {
+            asset_server.load(bevy::asset::AssetPath::new(path.into(), Some(label)))
+        })
+    }
+
+    /// A river whose meshes were put in owned storage with [`insert_meshes`] from the file at `path`
+    pub fn owned<P: LeEqU32, UV: VertexUV>(
+        path: &str,
+    ) -> River<P, UV, OwnedMeshes<FixedI32<P>, UV>>
+    where
+        FixedI32<P>: VertexPosition,
+    {
+        RiverObject::with_meshes(|label| mesh_key(path, &label))
+    }
 
+    /// A river with the handle `load` gives for each mesh name
+    fn with_meshes<P: LeEqU32, UV: VertexUV, S: MeshStorage<FixedI32<P>, UV> + ?Sized>(
+        mut load: impl FnMut(String) -> S::Handle,
+    ) -> River<P, UV, S>
+    where
+        FixedI32<P>: VertexPosition,
+    {
+        let mut meshes = HashMap::new();
         for connection in ConnectionType::iter() {
-            let path = AssetPath::new(base_path.clone(), Some(format!("{:?}", connection)));
-            meshes.insert(Connection::from(connection), asset_server.load(path));
+            meshes.insert(Connection::from(connection), load(format!("{:?}", connection)));
         }
-        WaveObject {
-            meshes,
-            can_connect_fn: RiverObject::can_connect,
-            build_fn: RiverObject::bake,
+        WaveObject::new(meshes, RiverObject)
+    }
+
+    /// The meshes a river file is missing according to `has`, which is given each mesh name.
+    /// A corner only needs itself or its mirror image, such as `CFW or CWF`
+    pub fn missing_meshes(has: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut missing = Vec::new();
+        for connection in ConnectionType::iter() {
+            let name: &'static str = connection.into();
+            if has(name) {
+                continue;
+            }
+            match connection.mirrored_corner() {
+                Some(mirror) if has(mirror.into()) => {}
+                // the pair is only reported once
+                Some(mirror) if (mirror as usize) < (connection as usize) => {}
+                Some(mirror) => missing.push(format!("{} or {}", name, <&str>::from(mirror))),
+                None => missing.push(name.to_string()),
+            }
         }
+        missing
     }
-    pub fn bake<'a, P: LeEqU32, UV: VertexUV>(
-        obj: &WaveObject<FixedI32<P>, UV, SeededWaveObjects<'a, FixedI32<P>, UV, 6>>,
+}
+
+impl<P: LeEqU32, UV: VertexUV, S: MeshStorage<FixedI32<P>, UV> + ?Sized>
+    WaveTile<FixedI32<P>, UV, SeededWaveObjects<FixedI32<P>, UV, S, 6>, S> for RiverObject
+where
+    FixedI32<P>: VertexPosition,
+{
+    fn build(
+        &self,
+        obj: &River<P, UV, S>,
         offset: RVec3<FixedI32<P>>,
-        meshs: &Assets<WaveMesh<FixedI32<P>, UV>>,
+        meshs: &S,
         main_mesh: &mut WaveBuilder<FixedI32<P>, UV>,
-        data: &SeededWaveObjects<'a, FixedI32<P>, UV, 6>,
-    ) -> Result<(), BakeError>
-    where
-        FixedI32<P>: VertexPosition,
-    {
+        data: &SeededWaveObjects<FixedI32<P>, UV, S, 6>,
+    ) -> Result<(), BakeError> {
         use ConnectionType::*;
         use HasConnection::*;
         main_mesh.bake(
             offset,
             meshs
-                .get(
+                .mesh(
                     obj.get(Core)
                         .ok_or(BakeError::MeshNotSet{ mesh: "Core", obj: "River"})?,
                 )
@@ -60,9 +105,11 @@ impl RiverObject {
         let sand_connection = Connection::new("Sand");
         let mut has_connection = [Flat; 6];
         for i in 0..6 {
-            if data.neighbours[i].can_connect(water_connection.clone()) {
+            // the side of the neighbour that faces this cell
+            let facing = (i + 3) % 6;
+            if data.neighbours[i].exposes(facing, &water_connection) {
                 has_connection[i] = Water;
-            } else if data.neighbours[i].can_connect(sand_connection.clone()) {
+            } else if data.neighbours[i].exposes(facing, &sand_connection) {
                 has_connection[i] = Sand;
             }
         }
@@ -79,46 +126,34 @@ impl RiverObject {
                     .ok_or(BakeError::MeshNotSet{ mesh: "Stright Sand", obj: "River"})?,
             };
             let mut stright = meshs
-                .get(stright)
+                .mesh(stright)
                 .ok_or(BakeError::MeshNotFound{ mesh: "Stright", obj: "River"})?
                 .clone();
             let cos = FixedI32::<P>::ROTATIONS_COS[i];
             let sin = FixedI32::<P>::ROTATIONS_SIN[i];
             stright.rotate(sin, cos);
             main_mesh.bake(offset, &stright)?;
-            let corner = match (has_connection[i], has_connection[(i + 1) % 6]) {
-                (Water, Water) => obj
-                    .get(CWW)
-                    .ok_or(BakeError::MeshNotSet{ mesh: "Corner Water Water", obj: "River"})?,
-                (Water, Flat) => obj
-                    .get(CWF)
-                    .ok_or(BakeError::MeshNotSet{ mesh: "Corner Water Flat", obj: "River"})?,
-                (Flat, Water) => obj
-                    .get(CFW)
-                    .ok_or(BakeError::MeshNotSet{ mesh: "Corner Flat Water", obj: "River"})?,
-                (Flat, Flat) => obj
-                    .get(CFF)
-                    .ok_or(BakeError::MeshNotSet{ mesh: "Corner Flat Flat", obj: "River"})?,
-                (Flat, Sand) => obj
-                    .get(CFS)
-                    .ok_or(BakeError::MeshNotSet{ mesh: "Corner Flat Sand", obj: "River"})?,
-                (Water, Sand) => obj
-                    .get(CWS)
-                    .ok_or(BakeError::MeshNotSet{ mesh: "Corner Water Sand", obj: "River"})?,
-                (Sand, Flat) => obj
-                    .get(CSF)
-                    .ok_or(BakeError::MeshNotSet{ mesh: "Corner Sand Flat", obj: "River"})?,
-                (Sand, Water) => obj
-                    .get(CSW)
-                    .ok_or(BakeError::MeshNotSet{ mesh: "Corner Sand Water", obj: "River"})?,
-                (Sand, Sand) => obj
-                    .get(CSS)
-                    .ok_or(BakeError::MeshNotSet{ mesh: "Corner Sand Sand", obj: "River"})?,
+            let (first, second) = (has_connection[i], has_connection[(i + 1) % 6]);
+            let mut corner = match corner_mesh(obj, meshs, first, second) {
+                Ok(corner) => corner.clone(),
+                Err(error) => {
+                    // a corner is the mirror image of the corner with its sides swapped,
+                    // so only one of CFW and CWF has to be modelled
+                    let mut corner = corner_mesh(obj, meshs, second, first)
+                        .map_err(|_| error)?
+                        .clone();
+                    // the plane through the center and the corner between side 0 and side 1
+                    corner.reflect(
+                        RVec3::new(
+                            FixedI32::<P>::ROTATIONS_COS[1],
+                            FixedI32::<P>::ZERO,
+                            FixedI32::<P>::ROTATIONS_SIN[1],
+                        ),
+                        FixedI32::<P>::ZERO,
+                    );
+                    corner
+                }
             };
-            let mut corner = meshs
-                .get(corner)
-                .ok_or(BakeError::MeshNotFound{ mesh: "Stright", obj: "River"})?
-                .clone();
             let cos = FixedI32::<P>::ROTATIONS_COS[i];
             let sin = FixedI32::<P>::ROTATIONS_SIN[i];
             corner.rotate(sin, cos);
@@ -126,9 +161,47 @@ impl RiverObject {
         }
         Ok(())
     }
-    pub fn can_connect(connection: Connection) -> bool {
-        connection == Connection::new("Water")
+
+    fn sockets(&self) -> Vec<Socket> {
+        // the river bends its banks to fit whatever is next to it
+        vec![Socket::wildcard("Water"); 6]
     }
+
+    fn reads_neighbours(&self) -> bool {
+        // the banks follow what the neighbours show
+        true
+    }
+}
+
+/// The corner mesh between a side showing `first` and the next side round showing `second`
+fn corner_mesh<'m, P: LeEqU32, UV: VertexUV, S: MeshStorage<FixedI32<P>, UV> + ?Sized>(
+    obj: &River<P, UV, S>,
+    meshs: &'m S,
+    first: HasConnection,
+    second: HasConnection,
+) -> Result<&'m WaveMesh<FixedI32<P>, UV>, BakeError>
+where
+    FixedI32<P>: VertexPosition,
+{
+    use ConnectionType::*;
+    use HasConnection::*;
+    let (connection, mesh) = match (first, second) {
+        (Water, Water) => (CWW, "Corner Water Water"),
+        (Water, Flat) => (CWF, "Corner Water Flat"),
+        (Flat, Water) => (CFW, "Corner Flat Water"),
+        (Flat, Flat) => (CFF, "Corner Flat Flat"),
+        (Flat, Sand) => (CFS, "Corner Flat Sand"),
+        (Water, Sand) => (CWS, "Corner Water Sand"),
+        (Sand, Flat) => (CSF, "Corner Sand Flat"),
+        (Sand, Water) => (CSW, "Corner Sand Water"),
+        (Sand, Sand) => (CSS, "Corner Sand Sand"),
+    };
+    meshs
+        .mesh(
+            obj.get(connection)
+                .ok_or(BakeError::MeshNotSet { mesh, obj: "River" })?,
+        )
+        .ok_or(BakeError::MeshNotFound { mesh, obj: "River" })
 }
 
 #[derive(Debug, Hash, IntoStaticStr, EnumIter, Clone, Copy)]
@@ -148,6 +221,23 @@ pub enum ConnectionType {
     CSS,
 }
 
+impl ConnectionType {
+    /// The corner that is the mirror image of this one with its sides swapped,
+    /// the river mirrors it when this corner has no mesh
+    pub fn mirrored_corner(self) -> Option<ConnectionType> {
+        use ConnectionType::*;
+        match self {
+            CFW => Some(CWF),
+            CWF => Some(CFW),
+            CFS => Some(CSF),
+            CSF => Some(CFS),
+            CWS => Some(CSW),
+            CSW => Some(CWS),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum HasConnection {
     Flat,
@@ -1,51 +1,93 @@
 use super::*;
 use crate::{
     errors::BakeError,
-    objects::Connection,
+    objects::{mesh_key, Connection, MeshStorage, Socket, WaveTile},
     prelude::RVec3,
     vertex::{VertexPosition, VertexUV},
-    wave_mesh::{WaveBuilder, WaveMesh},
+    wave_mesh::WaveBuilder,
 };
 use fixed::{types::extra::LeEqU32, FixedI32};
+#[derive(Debug, Clone, Copy, Default)]
 pub struct Sand;
 
 impl Sand {
+    /// Sand whose mesh is loaded by the `AssetServer` from the file at `path`
+    #[cfg(feature = "with_bevy")]
     pub fn new<'a, P: LeEqU32, UV: VertexUV, Data>(
-        asset_server: &AssetServer,
+        asset_server: &bevy::asset::AssetServer,
         path: &str,
-    ) -> WaveObject<FixedI32<P>, UV, Data>
+    ) -> WaveObject<FixedI32<P>, UV, Data, AssetMeshes<FixedI32<P>, UV>>
     where
         FixedI32<P>: VertexPosition,
     {
         let mut meshes = HashMap::new();
         meshes.insert(Connection::new("Core"), asset_server.load(path));
-        WaveObject {
-            meshes,
-            build_fn: Sand::bake,
-            can_connect_fn: Sand::can_connect,
-        }
+        WaveObject::new(meshes, Sand)
     }
-    pub fn bake<'a, P: LeEqU32, UV: VertexUV, Data>(
-        obj: &WaveObject<FixedI32<P>, UV, Data>,
-        offset: RVec3<FixedI32<P>>,
-        meshs: &Assets<WaveMesh<FixedI32<P>, UV>>,
-        main_mesh: &mut WaveBuilder<FixedI32<P>, UV>,
-        _neighbours: &Data,
-    ) -> Result<(), BakeError>
+
+    /// Sand whose mesh was put in owned storage with [`crate::objects::insert_meshes`] from the file at `path`,
+    /// the mesh is the object called `Core` in that file
+    pub fn owned<P: LeEqU32, UV: VertexUV, Data>(
+        path: &str,
+    ) -> WaveObject<FixedI32<P>, UV, Data, OwnedMeshes<FixedI32<P>, UV>>
     where
         FixedI32<P>: VertexPosition,
     {
+        let mut meshes = HashMap::new();
+        meshes.insert(Connection::new("Core"), mesh_key(path, "Core"));
+        WaveObject::new(meshes, Sand)
+    }
+}
+
+impl<P: LeEqU32, UV: VertexUV, Data, S: MeshStorage<FixedI32<P>, UV> + ?Sized>
+    WaveTile<FixedI32<P>, UV, Data, S> for Sand
+where
+    FixedI32<P>: VertexPosition,
+{
+    fn build(
+        &self,
+        obj: &WaveObject<FixedI32<P>, UV, Data, S>,
+        offset: RVec3<FixedI32<P>>,
+        meshs: &S,
+        main_mesh: &mut WaveBuilder<FixedI32<P>, UV>,
+        _neighbours: &Data,
+    ) -> Result<(), BakeError> {
         main_mesh.bake(
             offset,
             meshs
-                .get(
+                .mesh(
                     obj.get("Core")
                         .ok_or(BakeError::MeshNotSet{ mesh: "Core", obj: "Sand"})?,
                 )
                 .ok_or(BakeError::MeshNotFound{ mesh: "Core", obj: "Sand"})?,
         )
     }
-    pub fn can_connect(connection: Connection) -> bool {
-        connection == Connection::new("Sand")
+
+    fn sockets(&self) -> Vec<Socket> {
+        vec![Socket::new("Sand"); 6]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::insert_meshes;
+    use crate::wave_mesh::WaveMesh;
+    use fixed::types::extra::U16;
+
+    type P = FixedI32<U16>;
+
+    #[test]
+    fn owned_finds_core_in_the_file() {
+        let meshes =
+            WaveMesh::<P, u8>::from_obj_str("o Core\nv 0 0 0\nv 1 0 0\nv 0 0 1\nf 1 2 3\n")
+                .unwrap();
+        let mut storage = OwnedMeshes::default();
+        insert_meshes(&mut storage, "sand.obj", meshes);
+        let sand = Sand::owned::<U16, u8, ()>("sand.obj");
+        let mut builder = WaveBuilder::new();
+        sand.build(RVec3::default(), &storage, &mut builder, &())
+            .unwrap();
+        assert_eq!(builder.indices_len(), 3);
     }
 }
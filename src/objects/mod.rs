@@ -1,12 +1,24 @@
 use crate::prelude::*;
-use bevy::prelude::*;
 use std::{
     borrow::Cow,
     collections::HashMap,
     hash::{Hash, Hasher},
+    sync::Arc,
 };
 pub mod hexs_map;
-use crate::errors::BakeError;
+pub mod square_map;
+mod rotation;
+mod socket;
+mod storage;
+mod tile;
+pub use rotation::{GridTopology, ObjectRotation};
+pub use socket::{Socket, SocketKind};
+#[cfg(feature = "with_bevy")]
+pub use storage::{AssetMeshes, MeshSnapshot};
+pub use storage::{insert_meshes, mesh_key, MeshStorage, OwnedMeshes};
+pub use tile::{BuildFn, FnTile, WaveTile};
+use crate::errors::{BakeError, CollapseError};
+use crate::solver::{AdjacencyRules, CellGraph, Collapsed};
 
 #[derive(Clone)]
 pub struct Connection {
@@ -16,7 +28,8 @@ pub struct Connection {
 
 impl Connection {
     pub fn new<T: Into<Cow<'static, str>> + Hash>(id: T) -> Self {
-        let mut hasher = bevy::utils::AHasher::default();
+        // the std hasher with fixed keys, so the same name always gets the same hash
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
         let name: Cow<'static, str> = id.into();
         name.hash(&mut hasher);
         Connection {
@@ -54,47 +67,342 @@ impl std::fmt::Debug for Connection {
     }
 }
 
-pub struct WaveObjects<'a, P: VertexPosition, UV: VertexUV, const N: usize>(pub [&'a WaveObject<P, UV, Self>; N]);
+pub struct WaveObjects<'a, P: VertexPosition, UV: VertexUV, S: MeshStorage<P, UV> + ?Sized, const N: usize>(
+    pub [&'a WaveObject<P, UV, Self, S>; N],
+);
 
-pub struct SeededWaveObjects<'a, P: VertexPosition, UV: VertexUV, const N: usize> {
-    pub neighbours: [&'a WaveObject<P, UV, Self>; N],
+pub struct SeededWaveObjects<P: VertexPosition, UV: VertexUV, S: MeshStorage<P, UV> + ?Sized, const N: usize> {
+    /// `neighbours[i]` sits in direction `i`, for hex maps that is `HexId::DIRECTIONS[i]`
+    pub neighbours: [Arc<WaveObject<P, UV, Self, S>>; N],
     pub seed: u64,
 }
 
+impl<'a, P: VertexPosition, UV: VertexUV, S: MeshStorage<P, UV> + ?Sized, const N: usize>
+    WaveObjects<'a, P, UV, S, N>
+{
+    /// Gather the objects chosen for the neighbours of `cell` in a solved graph
+    /// `objects` is indexed by the tile index used in the rules the graph was solved with
+    /// `edge` is used for sides of the cell that have no neighbour
+    pub fn from_collapsed(
+        collapsed: &Collapsed,
+        graph: &CellGraph<N>,
+        cell: usize,
+        objects: &[&'a WaveObject<P, UV, Self, S>],
+        edge: &'a WaveObject<P, UV, Self, S>,
+    ) -> Self {
+        WaveObjects(std::array::from_fn(|direction| {
+            graph
+                .neighbour(cell, direction)
+                .map_or(edge, |neighbour| objects[collapsed.tile(neighbour)])
+        }))
+    }
+}
+
+impl<P: VertexPosition, UV: VertexUV, S: MeshStorage<P, UV> + ?Sized, const N: usize>
+    SeededWaveObjects<P, UV, S, N>
+{
+    /// Gather the objects chosen for the neighbours of `cell` in a solved graph
+    /// `objects` is indexed by the tile index used in the rules the graph was solved with
+    /// `edge` is used for sides of the cell that have no neighbour
+    pub fn from_collapsed(
+        collapsed: &Collapsed,
+        graph: &CellGraph<N>,
+        cell: usize,
+        objects: &[Arc<WaveObject<P, UV, Self, S>>],
+        edge: &Arc<WaveObject<P, UV, Self, S>>,
+        seed: u64,
+    ) -> Self {
+        SeededWaveObjects {
+            neighbours: std::array::from_fn(|direction| {
+                graph
+                    .neighbour(cell, direction)
+                    .map_or(edge, |neighbour| &objects[collapsed.tile(neighbour)])
+                    .clone()
+            }),
+            seed,
+        }
+    }
+}
+
 #[cfg(feature="bevy")]
-impl<P: VertexPosition, UV: VertexUV, DATA> bevy::reflect::TypeUuid for WaveObject<P, UV, DATA> {
+impl<P: VertexPosition, UV: VertexUV, DATA, S: MeshStorage<P, UV> + ?Sized> bevy::reflect::TypeUuid
+    for WaveObject<P, UV, DATA, S>
+{
     const TYPE_UUID: uuid::Uuid = uuid::uuid!("50baca88-21e3-47e8-9a4e-05fe89565e21");
 }
 
-pub struct WaveObject<P: VertexPosition, UV: VertexUV, DATA> {
-    pub meshes: HashMap<Connection, Handle<WaveMesh<P, UV>>>,
-    pub build_fn: fn(
-        &WaveObject<P, UV, DATA>,
-        RVec3<P>,
-        &Assets<WaveMesh<P, UV>>,
-        &mut WaveBuilder<P, UV>,
-        &DATA,
-    ) -> Result<(), BakeError>,
-    pub can_connect_fn: fn(Connection) -> bool,
+/// A piece of the map, `S` is the [`MeshStorage`] its meshes are kept in
+pub struct WaveObject<P: VertexPosition, UV: VertexUV, DATA, S: MeshStorage<P, UV> + ?Sized> {
+    pub meshes: HashMap<Connection, S::Handle>,
+    /// What the object is and how it is baked, shared between the variants made by [`WaveObject::rotations`]
+    pub tile: Arc<dyn WaveTile<P, UV, DATA, S>>,
+    /// What the object looks like from each of its sides, in the same direction order as the neighbours
+    pub sockets: Vec<Socket>,
+    /// How often the solver picks this object compared to the others, 1 by default
+    pub weight: f32,
+    /// Ask [`WaveObject::variants`] for mirrored variants as well as turned ones
+    pub mirrors: bool,
+    /// Set on variants made by [`WaveObject::rotations`] and [`WaveObject::mirrored`], the tile still bakes the authored orientation
+    /// and the result is mirrored and turned afterwards. Tiles that [`WaveTile::reads_neighbours`] never get variants
+    pub rotation: Option<ObjectRotation<P>>,
 }
 
-impl<P: VertexPosition, UV: VertexUV, DATA> WaveObject<P, UV, DATA> {
+impl<P: VertexPosition, UV: VertexUV, DATA, S: MeshStorage<P, UV> + ?Sized> Clone
+    for WaveObject<P, UV, DATA, S>
+{
+    fn clone(&self) -> Self {
+        WaveObject {
+            meshes: self.meshes.clone(),
+            tile: self.tile.clone(),
+            sockets: self.sockets.clone(),
+            weight: self.weight,
+            mirrors: self.mirrors,
+            rotation: self.rotation,
+        }
+    }
+}
+
+impl<P: VertexPosition, UV: VertexUV, DATA, S: MeshStorage<P, UV> + ?Sized> WaveObject<P, UV, DATA, S> {
+    /// Make an object from `tile` and the meshes it bakes with, the sockets, weight and mirrors start as the ones `tile` gives
+    pub fn new(
+        meshes: HashMap<Connection, S::Handle>,
+        tile: impl WaveTile<P, UV, DATA, S> + 'static,
+    ) -> Self {
+        WaveObject {
+            meshes,
+            sockets: tile.sockets(),
+            weight: tile.weight(),
+            mirrors: tile.mirrors(),
+            tile: Arc::new(tile),
+            rotation: None,
+        }
+    }
+
+    /// Make an object that is baked by a plain function
+    pub fn from_fn(
+        meshes: HashMap<Connection, S::Handle>,
+        build_fn: BuildFn<P, UV, DATA, S>,
+        sockets: Vec<Socket>,
+    ) -> Self
+    where
+        DATA: 'static,
+        S: 'static,
+    {
+        WaveObject::new(meshes, FnTile { build_fn, sockets })
+    }
+
     pub fn build(
         &self,
         offset: RVec3<P>,
-        meshs: &Assets<WaveMesh<P, UV>>,
+        meshs: &S,
         main_mesh: &mut WaveBuilder<P, UV>,
         neighbours: &DATA,
     ) -> Result<(), BakeError> {
-        (self.build_fn)(self, offset, meshs, main_mesh, neighbours)
+        let Some(rotation) = self.rotation else {
+            return self.tile.build(self, offset, meshs, main_mesh, neighbours);
+        };
+        let mut authored = WaveBuilder::new();
+        self.tile
+            .build(self, RVec3::default(), meshs, &mut authored, neighbours)?;
+        let mut mesh = authored.into_mesh();
+        if rotation.mirrored {
+            mesh.mirror_x();
+        }
+        mesh.rotate(rotation.sin, rotation.cos);
+        main_mesh.bake(offset, &mesh)
+    }
+    pub fn socket(&self, side: usize) -> Option<&Socket> {
+        self.sockets.get(side)
+    }
+    /// Can `socket` sit against `side` of this object
+    pub fn can_connect(&self, side: usize, socket: &Socket) -> bool {
+        self.socket(side).is_some_and(|s| s.connects(socket))
     }
-    pub fn can_connect(&self, connection: Connection) -> bool {
-        (self.can_connect_fn)(connection)
+    /// Does `side` of this object show `connection` to its neighbour, wildcard sockets still show their name
+    pub fn exposes(&self, side: usize, connection: &Connection) -> bool {
+        self.socket(side)
+            .is_some_and(|s| s.connection() == connection)
+    }
+    /// Set how often the solver picks this object compared to the others
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+    /// Set if [`WaveObject::variants`] should make mirrored variants of this object
+    pub fn with_mirrors(mut self, mirrors: bool) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Replace the sockets with ones worked out from how `mesh` meets each side of a cell on the grid `G`,
+    /// so two objects connect exactly when their meshes line up along the side they share.
+    /// Vertices within `tolerance` of a side count as on it, see [`WaveMesh::edge_profile`].
+    /// Sides that turns leave in place whose profile changes when turned get [`Socket::turning`] sockets
+    pub fn infer_sockets<G: GridTopology<P, N>, const N: usize>(
+        &mut self,
+        mesh: &WaveMesh<P, UV>,
+        tolerance: f32,
+    ) {
+        self.sockets = (0..N)
+            .map(|side| {
+                let profile = mesh.edge_profile(&G::edge_plane(side), tolerance);
+                let socket = Socket::from_profile(&profile);
+                let turns_in_place = G::TURNS > 1 && G::turn_side(side, 1) == side;
+                if turns_in_place && (G::TURNS != 4 || profile.quarter_turned() != profile) {
+                    socket.turning()
+                } else {
+                    socket
+                }
+            })
+            .collect();
+    }
+
+    /// Every distinct turn of this object on the grid `G`, starting with the object as it is,
+    /// so `object.rotations::<HexId, 6>()` gives up to 6 variants and `rotations::<SquareId, 4>` up to 4.
+    /// Turns whose sockets match an earlier turn are skipped so symmetric objects make fewer variants,
+    /// the weight is split between the variants so the object is picked as often as before.
+    /// Sides a turn leaves in place, like the top and bottom of a `CubeId` cell, keep their socket
+    /// but [`Socket::turning`] sockets remember the turn so they only meet sockets turned the same way.
+    /// Objects whose tile [`WaveTile::reads_neighbours`] only give themselves
+    pub fn rotations<G: GridTopology<P, N>, const N: usize>(&self) -> Vec<WaveObject<P, UV, DATA, S>> {
+        if self.sockets.len() != N || self.tile.reads_neighbours() {
+            return vec![self.clone()];
+        }
+        let mut variants: Vec<WaveObject<P, UV, DATA, S>> = Vec::with_capacity(G::TURNS);
+        let start = self.rotation.map_or(0, |rotation| rotation.steps);
+        let mirrored = self.rotation.is_some_and(|rotation| rotation.mirrored);
+        for steps in 0..G::TURNS {
+            let mut sockets = self.sockets.clone();
+            for (side, socket) in self.sockets.iter().enumerate() {
+                sockets[G::turn_side(side, steps)] = if G::turn_side(side, 1) == side {
+                    socket.turned(steps, G::TURNS)
+                } else {
+                    socket.clone()
+                };
+            }
+            if variants.iter().any(|variant| variant.sockets == sockets) {
+                continue;
+            }
+            let steps = (start + steps) % G::TURNS;
+            let mut variant = self.clone();
+            variant.sockets = sockets;
+            variant.rotation =
+                (steps != 0 || mirrored).then(|| ObjectRotation::new::<G, N>(steps, mirrored));
+            variants.push(variant);
+        }
+        let weight = self.weight / variants.len() as f32;
+        for variant in variants.iter_mut() {
+            variant.weight = weight;
+        }
+        variants
+    }
+
+    /// This object mirrored from x to -x on the grid `G`, so one chiral piece can stand in for its mirror image.
+    /// Asymmetric sockets are flipped since every side runs the other way once mirrored,
+    /// mirroring a mirrored variant gives back the unmirrored object.
+    /// Objects whose tile [`WaveTile::reads_neighbours`] are given back unmirrored
+    pub fn mirrored<G: GridTopology<P, N>, const N: usize>(&self) -> WaveObject<P, UV, DATA, S> {
+        let mut variant = self.clone();
+        if self.tile.reads_neighbours() {
+            return variant;
+        }
+        if self.sockets.len() == N {
+            for (side, socket) in self.sockets.iter().enumerate() {
+                variant.sockets[G::mirror_side(side)] = if G::turn_side(side, 1) == side {
+                    socket.mirrored(G::TURNS)
+                } else {
+                    socket.flipped()
+                };
+            }
+        }
+        let (steps, mirrored) = self
+            .rotation
+            .map_or((0, false), |rotation| (rotation.steps, rotation.mirrored));
+        // mirroring after a turn is the same as mirroring first and turning the other way
+        let steps = (G::TURNS - steps % G::TURNS) % G::TURNS;
+        variant.rotation =
+            (steps != 0 || !mirrored).then(|| ObjectRotation::new::<G, N>(steps, !mirrored));
+        variant
+    }
+
+    /// Every distinct turn of this object like [`WaveObject::rotations`],
+    /// followed by the turns of its mirror image when [`WaveObject::mirrors`] is set.
+    /// Mirror images that match a turn are skipped and the weight is split between all the variants
+    pub fn variants<G: GridTopology<P, N>, const N: usize>(&self) -> Vec<WaveObject<P, UV, DATA, S>> {
+        let mut variants = self.rotations::<G, N>();
+        if self.mirrors {
+            for variant in self.mirrored::<G, N>().rotations::<G, N>() {
+                if variants.iter().all(|other| other.sockets != variant.sockets) {
+                    variants.push(variant);
+                }
+            }
+        }
+        let weight = self.weight / variants.len() as f32;
+        for variant in variants.iter_mut() {
+            variant.weight = weight;
+        }
+        variants
     }
 }
 
-impl<P: VertexPosition, UV: VertexUV, DATA> WaveObject<P, UV, DATA> {
-    pub fn get<T: Into<&'static str>>(&self, connection: T) -> Option<&Handle<WaveMesh<P, UV>>>
+impl<const N: usize> AdjacencyRules<N> {
+    /// Build rules from the sockets and weights of objects, tile `i` of the rules is the `i`th object.
+    /// Two objects may be neighbours when the sockets on the sides facing each other connect,
+    /// two objects with the same name are an error since they would share a tile
+    pub fn from_objects<
+        'o,
+        T: Into<Connection>,
+        P: VertexPosition,
+        UV: VertexUV,
+        DATA: 'o,
+        S: MeshStorage<P, UV> + ?Sized + 'o,
+    >(
+        objects: impl IntoIterator<Item = (T, &'o WaveObject<P, UV, DATA, S>)>,
+    ) -> Result<AdjacencyRules<N>, CollapseError> {
+        let mut tiles = Vec::new();
+        for (name, object) in objects {
+            let name = name.into();
+            if tiles.iter().any(|(other, _, _)| *other == name) {
+                return Err(CollapseError::DuplicateTile(name.name().to_string()));
+            }
+            let sockets: [Socket; N] = object.sockets.clone().try_into().map_err(|_| {
+                CollapseError::MissingSockets {
+                    tile: name.name().to_string(),
+                    expected: N,
+                    found: object.sockets.len(),
+                }
+            })?;
+            tiles.push((name, sockets, object.weight));
+        }
+        let mut rules = AdjacencyRules::from_sockets(
+            tiles
+                .iter()
+                .map(|(name, sockets, _)| (name.clone(), sockets.clone())),
+        );
+        for (name, _, weight) in tiles.iter() {
+            rules.set_weight(name, *weight)?;
+        }
+        Ok(rules)
+    }
+
+    /// Copy the weight of each object onto the tile with the same index
+    pub fn weights_from_objects<P: VertexPosition, UV: VertexUV, DATA, S: MeshStorage<P, UV> + ?Sized>(
+        &mut self,
+        objects: &[&WaveObject<P, UV, DATA, S>],
+    ) -> Result<(), CollapseError> {
+        for (index, object) in objects.iter().enumerate() {
+            if index >= self.len() {
+                return Err(CollapseError::UnknownTile(format!("object {}", index)));
+            }
+            let tile = self.tile(index).clone();
+            self.set_weight(&tile, object.weight)?;
+        }
+        Ok(())
+    }
+}
+
+impl<P: VertexPosition, UV: VertexUV, DATA, S: MeshStorage<P, UV> + ?Sized> WaveObject<P, UV, DATA, S> {
+    pub fn get<T: Into<&'static str>>(&self, connection: T) -> Option<&S::Handle>
     where
         Connection: From<T>,
     {
@@ -109,3 +417,33 @@ impl<T: Into<&'static str> + Hash> From<T> for Connection {
         Connection::new(name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixed::{types::extra::U16, FixedI32};
+
+    type Object = WaveObject<FixedI32<U16>, u8, (), OwnedMeshes<FixedI32<U16>, u8>>;
+
+    fn object(socket: &'static str, weight: f32) -> Object {
+        Object::from_fn(HashMap::new(), |_, _, _, _, _| Ok(()), vec![Socket::new(socket); 4])
+            .with_weight(weight)
+    }
+
+    #[test]
+    fn rules_keep_object_order() {
+        let (sea, land) = (object("Sea", 2.), object("Land", 1.));
+        let rules = AdjacencyRules::<4>::from_objects([("Sea", &sea), ("Land", &land)]).unwrap();
+        assert_eq!(rules.tile(0), &Connection::from("Sea"));
+        assert_eq!(rules.weight(0), 2.);
+        assert!(rules.allowed(0, 0).contains(0));
+        assert!(!rules.allowed(0, 0).contains(1));
+    }
+
+    #[test]
+    fn rules_reject_duplicate_names() {
+        let (sea, land) = (object("Sea", 1.), object("Land", 1.));
+        let result = AdjacencyRules::<4>::from_objects([("Sea", &sea), ("Sea", &land)]);
+        assert!(matches!(result, Err(CollapseError::DuplicateTile(name)) if name == "Sea"));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::wave_mesh::EdgePlane;
+
+/// How the sides of a grid cell move when the cell is turned about the Y axis,
+/// implemented by the coordinate type of each grid such as `HexId` or `SquareId`
+pub trait GridTopology<P, const N: usize> {
+    /// How many turns it takes to get back to where you started
+    const TURNS: usize;
+    /// The side that `side` ends up on after turning `steps` times
+    fn turn_side(side: usize, steps: usize) -> usize;
+    /// The side that `side` ends up on when the cell is mirrored from x to -x,
+    /// every side then runs the other way along its edge
+    fn mirror_side(side: usize) -> usize;
+    /// Sin and cos of turning `steps` times
+    fn turn(steps: usize) -> (P, P);
+    /// Where `side` is for a tile modeled 1 cell across, used to find the edges of a mesh
+    fn edge_plane(side: usize) -> EdgePlane;
+}
+
+/// How far a variant made by [`super::WaveObject::rotations`] or [`super::WaveObject::mirrored`]
+/// is moved from the orientation it was authored in, mirrored first and then turned
+#[derive(Clone, Copy)]
+pub struct ObjectRotation<P> {
+    /// How many turns the object is turned by
+    pub steps: usize,
+    pub sin: P,
+    pub cos: P,
+    /// The object is mirrored from x to -x before it is turned
+    pub mirrored: bool,
+    turn_side: fn(usize, usize) -> usize,
+    mirror_side: fn(usize) -> usize,
+}
+
+impl<P> ObjectRotation<P> {
+    pub(crate) fn new<G: GridTopology<P, N>, const N: usize>(
+        steps: usize,
+        mirrored: bool,
+    ) -> ObjectRotation<P> {
+        let (sin, cos) = G::turn(steps);
+        ObjectRotation {
+            steps,
+            sin,
+            cos,
+            mirrored,
+            turn_side: G::turn_side,
+            mirror_side: G::mirror_side,
+        }
+    }
+
+    /// The side of the variant that side `side` of the authored object ends up on
+    pub fn map_side(&self, side: usize) -> usize {
+        let side = if self.mirrored {
+            (self.mirror_side)(side)
+        } else {
+            side
+        };
+        (self.turn_side)(side, self.steps)
+    }
+}
+
+impl<P: std::fmt::Debug> std::fmt::Debug for ObjectRotation<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObjectRotation")
+            .field("steps", &self.steps)
+            .field("sin", &self.sin)
+            .field("cos", &self.cos)
+            .field("mirrored", &self.mirrored)
+            .finish()
+    }
+}
@@ -0,0 +1,158 @@
+use std::borrow::Cow;
+
+use super::Connection;
+use crate::wave_mesh::EdgeProfile;
+
+/// How a [`Socket`] decides what it can sit against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SocketKind {
+    /// Connects to a symmetric socket with the same name
+    Symmetric,
+    /// Connects to an asymmetric socket with the same name that is flipped the other way, so "Bank" connects to "Bank'"
+    Asymmetric { flipped: bool },
+    /// Connects to anything, the name is still visible to neighbours
+    Wildcard,
+}
+
+/// What one side of an object looks like to its neighbour
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Socket {
+    connection: Connection,
+    kind: SocketKind,
+    /// How far the socket has been turned for sockets that look different once turned in place,
+    /// such as the top of a cube tile. `None` for sockets that look the same however they are turned
+    turn: Option<usize>,
+}
+
+impl Socket {
+    /// A socket that connects to other symmetric sockets with the same name
+    pub fn new<T: Into<Cow<'static, str>> + std::hash::Hash>(name: T) -> Socket {
+        Socket {
+            connection: Connection::new(name),
+            kind: SocketKind::Symmetric,
+            turn: None,
+        }
+    }
+
+    /// A socket that only connects to the [`Socket::flipped`] version of itself,
+    /// on a side that is turned in place it only connects to sockets turned the same way
+    pub fn asymmetric<T: Into<Cow<'static, str>> + std::hash::Hash>(name: T) -> Socket {
+        Socket {
+            connection: Connection::new(name),
+            kind: SocketKind::Asymmetric { flipped: false },
+            turn: Some(0),
+        }
+    }
+
+    /// A socket that connects to any other socket
+    pub fn wildcard<T: Into<Cow<'static, str>> + std::hash::Hash>(name: T) -> Socket {
+        Socket {
+            connection: Connection::new(name),
+            kind: SocketKind::Wildcard,
+            turn: None,
+        }
+    }
+
+    /// A socket that connects to sides that look the same as `profile` from the other cell.
+    /// Profiles that look the same from both sides make a symmetric socket, the rest are asymmetric
+    /// and named after whichever way round sorts first so a profile and its reverse share a name
+    pub fn from_profile(profile: &EdgeProfile) -> Socket {
+        let reversed = profile.reversed();
+        if *profile == reversed {
+            return Socket::new(format!("Edge({})", profile));
+        }
+        let flipped = reversed < *profile;
+        let name = if flipped { reversed } else { profile.clone() };
+        Socket {
+            connection: Connection::new(format!("Edge({})", name)),
+            kind: SocketKind::Asymmetric { flipped },
+            turn: Some(0),
+        }
+    }
+
+    /// This socket on a side that is turned in place, such as the top of a cube tile,
+    /// so it only connects to sockets turned the same way. Wildcards are unchanged
+    pub fn turning(mut self) -> Socket {
+        if self.kind != SocketKind::Wildcard {
+            self.turn.get_or_insert(0);
+        }
+        self
+    }
+
+    /// This socket turned `steps` more times on a side that is turned in place, out of `turns` to go all the way round
+    pub(crate) fn turned(&self, steps: usize, turns: usize) -> Socket {
+        Socket {
+            turn: self.turn.map(|turn| (turn + steps) % turns),
+            ..self.clone()
+        }
+    }
+
+    /// The same socket seen from the other way along the edge, "Bank" becomes "Bank'" and back again.
+    /// Symmetric and wildcard sockets are unchanged
+    pub fn flipped(&self) -> Socket {
+        let kind = match self.kind {
+            SocketKind::Asymmetric { flipped } => SocketKind::Asymmetric { flipped: !flipped },
+            kind => kind,
+        };
+        Socket {
+            connection: self.connection.clone(),
+            kind,
+            turn: self.turn,
+        }
+    }
+
+    /// This socket mirrored on a side that is turned in place, so it is turned the other way.
+    /// `turns` is how many turns go all the way round
+    pub(crate) fn mirrored(&self, turns: usize) -> Socket {
+        Socket {
+            turn: self.turn.map(|turn| (turns - turn % turns) % turns),
+            ..self.flipped()
+        }
+    }
+
+    /// Can `self` sit against `other`
+    pub fn connects(&self, other: &Socket) -> bool {
+        match (self.kind, other.kind) {
+            (SocketKind::Wildcard, _) | (_, SocketKind::Wildcard) => true,
+            _ if self.turn != other.turn => false,
+            (SocketKind::Symmetric, SocketKind::Symmetric) => self.connection == other.connection,
+            (SocketKind::Asymmetric { flipped: a }, SocketKind::Asymmetric { flipped: b }) => {
+                a != b && self.connection == other.connection
+            }
+            _ => false,
+        }
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn kind(&self) -> SocketKind {
+        self.kind
+    }
+
+    /// How far the socket has been turned in place, see [`Socket::turning`]
+    pub fn turn(&self) -> Option<usize> {
+        self.turn
+    }
+}
+
+impl std::fmt::Debug for Socket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self.connection.name();
+        match self.kind {
+            SocketKind::Symmetric => f.write_fmt(format_args!("Socket({}", name))?,
+            SocketKind::Asymmetric { flipped: false } => {
+                f.write_fmt(format_args!("Socket({}, Asymmetric", name))?
+            }
+            SocketKind::Asymmetric { flipped: true } => {
+                f.write_fmt(format_args!("Socket({}', Asymmetric", name))?
+            }
+            SocketKind::Wildcard => f.write_fmt(format_args!("Socket({}, Wildcard", name))?,
+        }
+        match self.turn {
+            Some(turn) if turn != 0 => f.write_fmt(format_args!(", Turned {})", turn)),
+            _ => f.write_str(")"),
+        }
+    }
+}
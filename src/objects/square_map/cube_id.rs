@@ -0,0 +1,235 @@
+use std::ops::{Add, Mul, Sub};
+
+use super::SquareTrig;
+use crate::{
+    objects::GridTopology, solver::CellGraph, vector::RVec3, vertex::VertexPosition,
+    wave_mesh::EdgePlane,
+};
+
+/// Coordinate of a cell on a cubic grid, a stack of square grids with up and down neighbours
+///
+/// The directions are +x, +z, +y then the opposites in the same order so `CellGraph::opposite` works.
+/// Turning about the Y axis walks +x, +z, -x, -z like a `SquareId` and turns up and down in place,
+/// see [`crate::objects::Socket::turning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct CubeId {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl CubeId {
+    pub const ZERO: CubeId = CubeId { x: 0, y: 0, z: 0 };
+
+    pub const UP: usize = 2;
+    pub const DOWN: usize = 5;
+
+    /// The offset to the neighbour in each direction
+    pub const DIRECTIONS: [CubeId; 6] = [
+        CubeId { x: 1, y: 0, z: 0 },
+        CubeId { x: 0, y: 0, z: 1 },
+        CubeId { x: 0, y: 1, z: 0 },
+        CubeId { x: -1, y: 0, z: 0 },
+        CubeId { x: 0, y: 0, z: -1 },
+        CubeId { x: 0, y: -1, z: 0 },
+    ];
+
+    /// The order the flat sides are visited when turning about the Y axis
+    const TURN_ORDER: [usize; 4] = [0, 1, 3, 4];
+
+    pub const fn new(x: i32, y: i32, z: i32) -> CubeId {
+        CubeId { x, y, z }
+    }
+
+    /// The neighbouring cell in `direction`, wraps around so any usize is valid
+    pub fn neighbour(&self, direction: usize) -> CubeId {
+        *self + CubeId::DIRECTIONS[direction % 6]
+    }
+
+    pub fn neighbours(&self) -> [CubeId; 6] {
+        CubeId::DIRECTIONS.map(|direction| *self + direction)
+    }
+
+    /// The number of steps between two cells moving only through faces
+    pub fn distance(&self, other: CubeId) -> u32 {
+        (self.x - other.x).unsigned_abs()
+            + (self.y - other.y).unsigned_abs()
+            + (self.z - other.z).unsigned_abs()
+    }
+
+    /// Turn about the Y axis through the origin by `steps` quarter turns, +x goes to +z
+    pub fn rotate(&self, steps: usize) -> CubeId {
+        let mut cube = *self;
+        for _ in 0..steps % 4 {
+            cube = CubeId {
+                x: -cube.z,
+                y: cube.y,
+                z: cube.x,
+            };
+        }
+        cube
+    }
+
+    /// The position of the center of the cell for tiles modeled 1 unit across
+    pub fn world_offset<P: VertexPosition>(&self) -> RVec3<P> {
+        RVec3::new(
+            P::from_f32(self.x as f32),
+            P::from_f32(self.y as f32),
+            P::from_f32(self.z as f32),
+        )
+    }
+
+    /// Every cell in a `width` by `height` by `depth` box starting at the origin, layer by layer from the bottom
+    pub fn block(width: u32, height: u32, depth: u32) -> impl Iterator<Item = CubeId> {
+        (0..height as i32).flat_map(move |y| {
+            (0..depth as i32).flat_map(move |z| (0..width as i32).map(move |x| CubeId { x, y, z }))
+        })
+    }
+
+    /// Build a graph from a list of cells, cell `i` in the graph is `cells[i]`
+    pub fn graph(cells: &[CubeId]) -> CellGraph<6> {
+        CellGraph::from_cells(cells, CubeId::neighbour)
+    }
+}
+
+impl<P: SquareTrig + Copy> GridTopology<P, 6> for CubeId {
+    const TURNS: usize = 4;
+    fn turn_side(side: usize, steps: usize) -> usize {
+        match CubeId::TURN_ORDER.iter().position(|s| *s == side) {
+            Some(i) => CubeId::TURN_ORDER[(i + steps) % 4],
+            None => side,
+        }
+    }
+    fn mirror_side(side: usize) -> usize {
+        match side % 6 {
+            0 => 3,
+            3 => 0,
+            side => side,
+        }
+    }
+    fn turn(steps: usize) -> (P, P) {
+        (P::ROTATIONS_SIN[steps % 4], P::ROTATIONS_COS[steps % 4])
+    }
+    fn edge_plane(side: usize) -> EdgePlane {
+        match side % 6 {
+            CubeId::UP => EdgePlane::top(0.5),
+            CubeId::DOWN => EdgePlane::bottom(0.5),
+            side => {
+                let CubeId { x, z, .. } = CubeId::DIRECTIONS[side];
+                EdgePlane::side(x as f32, z as f32, 0.5)
+            }
+        }
+    }
+}
+
+impl Add for CubeId {
+    type Output = CubeId;
+    fn add(self, rhs: CubeId) -> CubeId {
+        CubeId {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl Sub for CubeId {
+    type Output = CubeId;
+    fn sub(self, rhs: CubeId) -> CubeId {
+        CubeId {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl Mul<i32> for CubeId {
+    type Output = CubeId;
+    fn mul(self, rhs: i32) -> CubeId {
+        CubeId {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        objects::{OwnedMeshes, Socket, WaveObject},
+        wave_mesh::WaveMesh,
+    };
+    use fixed::{types::extra::U16, FixedI32};
+
+    type P = FixedI32<U16>;
+    type Object = WaveObject<P, u8, (), OwnedMeshes<P, u8>>;
+
+    /// A post off to one side of the cell running from the floor to the ceiling, so it stacks on itself
+    const POST: &str = "o Post
+v 0.1 0.5 0.2
+v 0.4 0.5 0.2
+v 0.1 0.5 0.3
+v 0.1 -0.5 0.2
+v 0.4 -0.5 0.2
+v 0.1 -0.5 0.3
+f 1 2 3
+f 4 6 5
+";
+
+    fn object(sockets: Vec<Socket>) -> Object {
+        Object::from_fn(HashMap::new(), |_, _, _, _, _| Ok(()), sockets)
+    }
+
+    #[test]
+    fn turned_tiles_stack_like_their_meshes() {
+        let mesh = WaveMesh::<P, u8>::from_obj_str(POST)
+            .unwrap()
+            .remove("Post")
+            .unwrap();
+        let mut post = object(Vec::new());
+        post.infer_sockets::<CubeId, 6>(&mesh, 0.01);
+        let variants = post.rotations::<CubeId, 6>();
+        assert_eq!(variants.len(), 4);
+        let (top, bottom) = (EdgePlane::top(0.5), EdgePlane::bottom(0.5));
+        let profiles: Vec<_> = variants
+            .iter()
+            .map(|variant| {
+                let mut mesh = mesh.clone();
+                if let Some(rotation) = variant.rotation {
+                    mesh.rotate(rotation.sin, rotation.cos);
+                }
+                (
+                    mesh.edge_profile(&top, 0.01),
+                    mesh.edge_profile(&bottom, 0.01),
+                )
+            })
+            .collect();
+        for (below, (below_top, _)) in variants.iter().zip(profiles.iter()) {
+            for (above, (_, above_bottom)) in variants.iter().zip(profiles.iter()) {
+                let lines_up = *below_top == above_bottom.reversed();
+                let connects = below.sockets[CubeId::UP].connects(&above.sockets[CubeId::DOWN]);
+                assert_eq!(connects, lines_up);
+            }
+        }
+    }
+
+    #[test]
+    fn turned_tiles_keep_plain_tops() {
+        let mut sockets = vec![Socket::new("Wall"); 6];
+        sockets[0] = Socket::new("Door");
+        sockets[CubeId::UP] = Socket::new("Air");
+        sockets[CubeId::DOWN] = Socket::new("Air");
+        let variants = object(sockets).rotations::<CubeId, 6>();
+        assert_eq!(variants.len(), 4);
+        for below in variants.iter() {
+            for above in variants.iter() {
+                assert!(below.sockets[CubeId::UP].connects(&above.sockets[CubeId::DOWN]));
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+mod cube_id;
+mod square_id;
+pub use cube_id::*;
+pub use square_id::*;
+pub use trig::*;
+
+mod trig {
+    use fixed::{
+        types::extra::{LeEqU32, LeEqU64},
+        FixedI32, FixedI64,
+    };
+
+    use crate::vertex::Quantised;
+
+    pub trait SquareTrig: Sized {
+        const ROTATIONS_COS: [Self; 4];
+        const ROTATIONS_SIN: [Self; 4];
+    }
+    impl<P: LeEqU32> SquareTrig for FixedI32<P> {
+        const ROTATIONS_COS: [FixedI32<P>; 4] = [
+            FixedI32::<P>::lit("1."),
+            FixedI32::<P>::lit("0."),
+            FixedI32::<P>::lit("-1."),
+            FixedI32::<P>::lit("0."),
+        ];
+        const ROTATIONS_SIN: [FixedI32<P>; 4] = [
+            FixedI32::<P>::lit("0."),
+            FixedI32::<P>::lit("1."),
+            FixedI32::<P>::lit("0."),
+            FixedI32::<P>::lit("-1."),
+        ];
+    }
+
+    impl<P: LeEqU64> SquareTrig for FixedI64<P> {
+        const ROTATIONS_COS: [FixedI64<P>; 4] = [
+            FixedI64::<P>::lit("1."),
+            FixedI64::<P>::lit("0."),
+            FixedI64::<P>::lit("-1."),
+            FixedI64::<P>::lit("0."),
+        ];
+        const ROTATIONS_SIN: [FixedI64<P>; 4] = [
+            FixedI64::<P>::lit("0."),
+            FixedI64::<P>::lit("1."),
+            FixedI64::<P>::lit("0."),
+            FixedI64::<P>::lit("-1."),
+        ];
+    }
+
+    impl SquareTrig for f32 {
+        const ROTATIONS_COS: [f32; 4] = [1., 0., -1., 0.];
+        const ROTATIONS_SIN: [f32; 4] = [0., 1., 0., -1.];
+    }
+
+    impl SquareTrig for f64 {
+        const ROTATIONS_COS: [f64; 4] = [1., 0., -1., 0.];
+        const ROTATIONS_SIN: [f64; 4] = [0., 1., 0., -1.];
+    }
+
+    impl<F: SquareTrig + Copy, const STEPS: u32> SquareTrig for Quantised<F, STEPS> {
+        const ROTATIONS_COS: [Quantised<F, STEPS>; 4] = {
+            let values = F::ROTATIONS_COS;
+            [
+                Quantised(values[0]),
+                Quantised(values[1]),
+                Quantised(values[2]),
+                Quantised(values[3]),
+            ]
+        };
+        const ROTATIONS_SIN: [Quantised<F, STEPS>; 4] = {
+            let values = F::ROTATIONS_SIN;
+            [
+                Quantised(values[0]),
+                Quantised(values[1]),
+                Quantised(values[2]),
+                Quantised(values[3]),
+            ]
+        };
+    }
+}
@@ -0,0 +1,125 @@
+use std::ops::{Add, Mul, Sub};
+
+use super::SquareTrig;
+use crate::{
+    objects::GridTopology, solver::CellGraph, vector::RVec3, vertex::VertexPosition,
+    wave_mesh::EdgePlane,
+};
+
+/// Coordinate of a cell on a flat square grid laid out on the x and z axes
+///
+/// Neighbour direction `i` is the side a mesh faces once rotated by
+/// `SquareTrig::ROTATIONS_SIN[i]`/`ROTATIONS_COS[i]`, direction 0 is +x and direction 1 is +z.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct SquareId {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl SquareId {
+    pub const ZERO: SquareId = SquareId { x: 0, z: 0 };
+
+    /// The offset to the neighbour in each direction, in the same order as `SquareTrig::ROTATIONS_SIN/COS`
+    pub const DIRECTIONS: [SquareId; 4] = [
+        SquareId { x: 1, z: 0 },
+        SquareId { x: 0, z: 1 },
+        SquareId { x: -1, z: 0 },
+        SquareId { x: 0, z: -1 },
+    ];
+
+    pub const fn new(x: i32, z: i32) -> SquareId {
+        SquareId { x, z }
+    }
+
+    /// The neighbouring cell in `direction`, wraps around so any usize is valid
+    pub fn neighbour(&self, direction: usize) -> SquareId {
+        *self + SquareId::DIRECTIONS[direction % 4]
+    }
+
+    pub fn neighbours(&self) -> [SquareId; 4] {
+        SquareId::DIRECTIONS.map(|direction| *self + direction)
+    }
+
+    /// The number of steps between two cells moving only through sides
+    pub fn distance(&self, other: SquareId) -> u32 {
+        (self.x - other.x).unsigned_abs() + (self.z - other.z).unsigned_abs()
+    }
+
+    /// Rotate around the origin by `steps` directions, so `DIRECTIONS[i]` becomes `DIRECTIONS[i + steps]`
+    pub fn rotate(&self, steps: usize) -> SquareId {
+        let mut square = *self;
+        for _ in 0..steps % 4 {
+            square = SquareId {
+                x: -square.z,
+                z: square.x,
+            };
+        }
+        square
+    }
+
+    /// The position of the center of the cell for tiles modeled 1 unit across
+    pub fn world_offset<P: VertexPosition>(&self) -> RVec3<P> {
+        RVec3::new(
+            P::from_f32(self.x as f32),
+            P::default(),
+            P::from_f32(self.z as f32),
+        )
+    }
+
+    /// Every cell in a `width` by `depth` rectangle starting at the origin, row by row along x
+    pub fn rect(width: u32, depth: u32) -> impl Iterator<Item = SquareId> {
+        (0..depth as i32).flat_map(move |z| (0..width as i32).map(move |x| SquareId { x, z }))
+    }
+
+    /// Build a graph from a list of cells, cell `i` in the graph is `cells[i]`
+    pub fn graph(cells: &[SquareId]) -> CellGraph<4> {
+        CellGraph::from_cells(cells, SquareId::neighbour)
+    }
+}
+
+impl<P: SquareTrig + Copy> GridTopology<P, 4> for SquareId {
+    const TURNS: usize = 4;
+    fn turn_side(side: usize, steps: usize) -> usize {
+        (side + steps) % 4
+    }
+    fn mirror_side(side: usize) -> usize {
+        (6 - side % 4) % 4
+    }
+    fn turn(steps: usize) -> (P, P) {
+        (P::ROTATIONS_SIN[steps % 4], P::ROTATIONS_COS[steps % 4])
+    }
+    fn edge_plane(side: usize) -> EdgePlane {
+        let SquareId { x, z } = SquareId::DIRECTIONS[side % 4];
+        EdgePlane::side(x as f32, z as f32, 0.5)
+    }
+}
+
+impl Add for SquareId {
+    type Output = SquareId;
+    fn add(self, rhs: SquareId) -> SquareId {
+        SquareId {
+            x: self.x + rhs.x,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl Sub for SquareId {
+    type Output = SquareId;
+    fn sub(self, rhs: SquareId) -> SquareId {
+        SquareId {
+            x: self.x - rhs.x,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl Mul<i32> for SquareId {
+    type Output = SquareId;
+    fn mul(self, rhs: i32) -> SquareId {
+        SquareId {
+            x: self.x * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+#[cfg(feature = "with_bevy")]
+use std::sync::Arc;
+
+use super::Connection;
+use crate::{
+    vertex::{VertexPosition, VertexUV},
+    wave_mesh::WaveMesh,
+};
+
+/// Where the meshes objects bake with are kept, [`super::WaveObject::build`] is given the storage
+/// and each object holds a handle into it for every mesh it uses.
+/// Bevy `Assets` hold meshes loaded by the `AssetServer`, a plain `HashMap<Connection, WaveMesh>` works without Bevy
+pub trait MeshStorage<P: VertexPosition, UV: VertexUV>: Send + Sync {
+    /// How an object points at one of its meshes
+    type Handle: Clone + Send + Sync;
+
+    /// The mesh `handle` points at, `None` if it is not loaded
+    fn mesh(&self, handle: &Self::Handle) -> Option<&WaveMesh<P, UV>>;
+}
+
+#[cfg(feature = "with_bevy")]
+impl<P: VertexPosition, UV: VertexUV> MeshStorage<P, UV> for AssetMeshes<P, UV> {
+    type Handle = bevy::prelude::Handle<WaveMesh<P, UV>>;
+
+    fn mesh(&self, handle: &Self::Handle) -> Option<&WaveMesh<P, UV>> {
+        self.get(handle)
+    }
+}
+
+/// Meshes kept without Bevy, objects point at them by key, see [`mesh_key`]
+pub type OwnedMeshes<P, UV> = HashMap<Connection, WaveMesh<P, UV>>;
+
+/// Meshes loaded by the Bevy `AssetServer`
+#[cfg(feature = "with_bevy")]
+pub type AssetMeshes<P, UV> = bevy::prelude::Assets<WaveMesh<P, UV>>;
+
+/// Meshes copied out of [`AssetMeshes`] so objects can be baked away from the `World`, such as on another thread.
+/// Objects look meshes up with the same handles as in the assets, clones share the copied meshes
+#[cfg(feature = "with_bevy")]
+pub struct MeshSnapshot<P: VertexPosition, UV: VertexUV> {
+    meshes: Arc<HashMap<bevy::asset::HandleId, WaveMesh<P, UV>>>,
+}
+
+#[cfg(feature = "with_bevy")]
+impl<P: VertexPosition, UV: VertexUV> MeshSnapshot<P, UV> {
+    /// Copy the meshes `handles` point at, handles that are not loaded are left out
+    pub fn new<'h>(
+        assets: &AssetMeshes<P, UV>,
+        handles: impl IntoIterator<Item = &'h bevy::prelude::Handle<WaveMesh<P, UV>>>,
+    ) -> Self {
+        let meshes = handles
+            .into_iter()
+            .filter_map(|handle| Some((handle.id(), assets.get(handle)?.clone())))
+            .collect();
+        MeshSnapshot {
+            meshes: Arc::new(meshes),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.meshes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.meshes.is_empty()
+    }
+}
+
+#[cfg(feature = "with_bevy")]
+impl<P: VertexPosition, UV: VertexUV> Clone for MeshSnapshot<P, UV> {
+    fn clone(&self) -> Self {
+        MeshSnapshot {
+            meshes: self.meshes.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "with_bevy")]
+impl<P: VertexPosition, UV: VertexUV> MeshStorage<P, UV> for MeshSnapshot<P, UV> {
+    type Handle = bevy::prelude::Handle<WaveMesh<P, UV>>;
+
+    fn mesh(&self, handle: &Self::Handle) -> Option<&WaveMesh<P, UV>> {
+        self.meshes.get(&handle.id())
+    }
+}
+
+impl<P: VertexPosition, UV: VertexUV> MeshStorage<P, UV> for OwnedMeshes<P, UV> {
+    type Handle = Connection;
+
+    fn mesh(&self, handle: &Self::Handle) -> Option<&WaveMesh<P, UV>> {
+        self.get(handle)
+    }
+}
+
+/// The key owned storage keeps mesh `label` of the file at `path` under,
+/// written like a Bevy asset path with a label so `path#label`
+pub fn mesh_key(path: &str, label: &str) -> Connection {
+    Connection::new(format!("{}#{}", path, label))
+}
+
+/// Put meshes read from the file at `path`, such as the map from [`WaveMesh::from_obj_str`],
+/// into owned storage under [`mesh_key`] where objects made with `owned(path)` look for them
+pub fn insert_meshes<P: VertexPosition, UV: VertexUV>(
+    storage: &mut OwnedMeshes<P, UV>,
+    path: &str,
+    meshes: impl IntoIterator<Item = (String, WaveMesh<P, UV>)>,
+) {
+    for (label, mesh) in meshes {
+        storage.insert(mesh_key(path, &label), mesh);
+    }
+}
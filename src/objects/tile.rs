@@ -0,0 +1,76 @@
+use super::{MeshStorage, Socket, WaveObject};
+use crate::{
+    errors::BakeError,
+    vector::RVec3,
+    vertex::{VertexPosition, VertexUV},
+    wave_mesh::WaveBuilder,
+};
+
+/// The signature of a plain function that bakes an object, see [`FnTile`]
+pub type BuildFn<P, UV, DATA, S> = fn(
+    &WaveObject<P, UV, DATA, S>,
+    RVec3<P>,
+    &S,
+    &mut WaveBuilder<P, UV>,
+    &DATA,
+) -> Result<(), BakeError>;
+
+/// What a [`WaveObject`] is, implement this on a struct to give an object its own settings
+/// instead of passing everything through `DATA`
+pub trait WaveTile<P: VertexPosition, UV: VertexUV, DATA, S: MeshStorage<P, UV> + ?Sized>: Send + Sync {
+    /// Bake the object at `offset` into `main_mesh`, `obj` holds handles into `meshs` for the meshes the object was loaded with
+    fn build(
+        &self,
+        obj: &WaveObject<P, UV, DATA, S>,
+        offset: RVec3<P>,
+        meshs: &S,
+        main_mesh: &mut WaveBuilder<P, UV>,
+        data: &DATA,
+    ) -> Result<(), BakeError>;
+
+    /// What the object looks like from each of its sides, copied into [`WaveObject::sockets`] when the object is made
+    fn sockets(&self) -> Vec<Socket>;
+
+    /// How often the solver picks this object compared to the others, copied into [`WaveObject::weight`]
+    fn weight(&self) -> f32 {
+        1.
+    }
+
+    /// True when the object should also be placed as its mirror image, copied into [`WaveObject::mirrors`]
+    fn mirrors(&self) -> bool {
+        false
+    }
+
+    /// True when `build` reads the sides of its neighbours from `data`. Neighbours are always in map order
+    /// while a turned variant still bakes its authored orientation, so [`WaveObject::rotations`]
+    /// and [`WaveObject::mirrored`] leave these objects as they are
+    fn reads_neighbours(&self) -> bool {
+        false
+    }
+}
+
+/// Adapter for objects that are just a build function and a list of sockets,
+/// they can be turned so the function should not read the sides of its neighbours
+pub struct FnTile<P: VertexPosition, UV: VertexUV, DATA, S: MeshStorage<P, UV> + ?Sized> {
+    pub build_fn: BuildFn<P, UV, DATA, S>,
+    pub sockets: Vec<Socket>,
+}
+
+impl<P: VertexPosition, UV: VertexUV, DATA, S: MeshStorage<P, UV> + ?Sized> WaveTile<P, UV, DATA, S>
+    for FnTile<P, UV, DATA, S>
+{
+    fn build(
+        &self,
+        obj: &WaveObject<P, UV, DATA, S>,
+        offset: RVec3<P>,
+        meshs: &S,
+        main_mesh: &mut WaveBuilder<P, UV>,
+        data: &DATA,
+    ) -> Result<(), BakeError> {
+        (self.build_fn)(obj, offset, meshs, main_mesh, data)
+    }
+
+    fn sockets(&self) -> Vec<Socket> {
+        self.sockets.clone()
+    }
+}
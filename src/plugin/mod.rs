@@ -0,0 +1,382 @@
+use std::{marker::PhantomData, str::FromStr, sync::Arc};
+
+use bevy::{
+    asset::LoadState,
+    ecs::system::SystemParam,
+    prelude::*,
+    render::render_resource::PrimitiveTopology,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+
+mod streaming;
+mod tasks;
+use streaming::stream_chunks;
+pub use streaming::{ChunkFailed, ChunkFocus, ChunkStreaming, MapChunk};
+use tasks::{finish_map_tasks, start_map_tasks};
+pub use tasks::{GenerateMapAsync, MapTask, MapTaskFailed};
+
+use crate::{
+    errors::GenerateError,
+    objects::{
+        hexs_map::{cell_seed, HexId, HexObject, HexTrig},
+        AssetMeshes, Connection, MeshSnapshot, MeshStorage, SeededWaveObjects,
+    },
+    prelude::*,
+};
+
+/// Any storage map objects find their meshes in by asset handle, the [`AssetMeshes`] themselves or a [`MeshSnapshot`] of them
+pub type MapMeshes<P, UV> = dyn MeshStorage<P, UV, Handle = Handle<WaveMesh<P, UV>>>;
+
+/// A hex map object as the plugin keeps it
+pub type MapObject<P, UV> = HexObject<P, UV, MapMeshes<P, UV>>;
+
+/// Registers [`WaveMesh`] as an asset with its `.wfo` and `.wfb` loaders,
+/// adds the [`WaveObjectRegistry`] resource and turns [`GenerateMap`] events into map entities.
+/// Insert a [`ChunkStreaming`] resource to stream a map around [`ChunkFocus`] entities instead,
+/// or put a [`GenerateMapAsync`] on an entity to build its map without holding up the frame.
+/// Every plugin answers every request so add one per app
+pub struct WaveCollapsePlugin<P, UV: VertexUV> {
+    /// Used by the `.wfo` loader to turn material names into UVs
+    pub palette: MaterialPalette<UV>,
+    _position: PhantomData<P>,
+}
+
+impl<P, UV: VertexUV> Default for WaveCollapsePlugin<P, UV> {
+    fn default() -> Self {
+        WaveCollapsePlugin::new(MaterialPalette::default())
+    }
+}
+
+impl<P, UV: VertexUV> WaveCollapsePlugin<P, UV> {
+    pub fn new(palette: MaterialPalette<UV>) -> Self {
+        WaveCollapsePlugin {
+            palette,
+            _position: PhantomData,
+        }
+    }
+}
+
+impl<P, UV> Plugin for WaveCollapsePlugin<P, UV>
+where
+    P: VertexPosition + HexTrig + RawBits + FromStr,
+    UV: VertexUV + RawBits + FromStr + Default,
+{
+    fn build(&self, app: &mut App) {
+        app.add_asset::<WaveMesh<P, UV>>()
+            .add_asset_loader(WaveMeshObjLoader::<P, UV>::new(self.palette.clone()))
+            .add_asset_loader(WaveMeshBinaryLoader::<P, UV>::default())
+            .init_resource::<WaveObjectRegistry<P, UV>>()
+            .add_event::<GenerateMap>()
+            .add_event::<MapFailed>()
+            .add_event::<ChunkFailed>()
+            .add_event::<MapTaskFailed>()
+            .add_system(generate_maps::<P, UV>)
+            .add_system(stream_chunks::<P, UV>)
+            .add_system(start_map_tasks::<P, UV>)
+            .add_system(finish_map_tasks.after(start_map_tasks::<P, UV>));
+    }
+}
+
+/// The objects maps are built from, tile `i` of the rules is the `i`th object registered
+#[derive(Resource)]
+pub struct WaveObjectRegistry<P: VertexPosition, UV: VertexUV> {
+    objects: Vec<(Connection, Arc<MapObject<P, UV>>)>,
+    edge: Option<Arc<MapObject<P, UV>>>,
+}
+
+impl<P: VertexPosition, UV: VertexUV> Default for WaveObjectRegistry<P, UV> {
+    fn default() -> Self {
+        WaveObjectRegistry {
+            objects: Vec::new(),
+            edge: None,
+        }
+    }
+}
+
+impl<P: VertexPosition + HexTrig, UV: VertexUV> WaveObjectRegistry<P, UV> {
+    /// Add an object the solver can place called `name`, an object already called `name` is replaced.
+    /// A replaced object is freed once the maps still being built with it are done
+    pub fn register(&mut self, name: impl Into<Connection>, object: MapObject<P, UV>) {
+        let name = name.into();
+        let object = Arc::new(object);
+        match self.objects.iter_mut().find(|(other, _)| *other == name) {
+            Some(slot) => slot.1 = object,
+            None => self.objects.push((name, object)),
+        }
+    }
+
+    /// Register every variant of `object` from [`WaveObject::variants`],
+    /// the first is called `name` and the rest `name#1`, `name#2` and so on
+    pub fn register_variants(&mut self, name: &str, object: MapObject<P, UV>) {
+        for (index, variant) in object.variants::<HexId, 6>().into_iter().enumerate() {
+            let name = match index {
+                0 => name.to_string(),
+                index => format!("{}#{}", name, index),
+            };
+            self.register(Connection::new(name), variant);
+        }
+    }
+
+    /// The object sides with no neighbour see, without one a cell sees itself past the edge of the map
+    pub fn set_edge(&mut self, object: MapObject<P, UV>) {
+        self.edge = Some(Arc::new(object));
+    }
+
+    pub fn get(&self, name: &Connection) -> Option<&MapObject<P, UV>> {
+        self.objects
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, object)| &**object)
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = (&Connection, &MapObject<P, UV>)> {
+        self.objects.iter().map(|(name, object)| (name, &**object))
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// The object set with [`WaveObjectRegistry::set_edge`]
+    pub fn edge(&self) -> Option<&MapObject<P, UV>> {
+        self.edge.as_deref()
+    }
+
+    /// Rules for the registered objects, tile `i` is the `i`th object registered
+    pub fn rules(&self) -> Result<AdjacencyRules<6>, GenerateError> {
+        if self.objects.is_empty() {
+            return Err(GenerateError::NoObjects);
+        }
+        Ok(AdjacencyRules::from_objects(
+            self.objects
+                .iter()
+                .map(|(name, object)| (name.clone(), &**object)),
+        )?)
+    }
+
+    /// The registered objects indexed by tile
+    fn tiles(&self) -> Vec<Arc<MapObject<P, UV>>> {
+        self.objects.iter().map(|(_, object)| object.clone()).collect()
+    }
+
+    /// Every mesh handle used by the registered objects and the edge
+    pub fn handles(&self) -> impl Iterator<Item = &Handle<WaveMesh<P, UV>>> {
+        self.objects
+            .iter()
+            .map(|(_, object)| object)
+            .chain(&self.edge)
+            .flat_map(|object| object.meshes.values())
+    }
+
+    /// Solve `cells` with the registered objects and bake the map, each cell is built at [`HexId::world_offset`]
+    /// and its neighbours get a seed made from `seed` and the cell so the same map always looks the same.
+    /// Contradictions are recovered from with `recovery`
+    pub fn generate(
+        &self,
+        cells: &[HexId],
+        seed: u64,
+        recovery: Recovery,
+        meshes: &MapMeshes<P, UV>,
+    ) -> Result<WaveBuilder<P, UV>, GenerateError> {
+        bake_map(
+            &self.rules()?,
+            &self.tiles(),
+            self.edge.as_ref(),
+            cells,
+            seed,
+            recovery,
+            meshes,
+        )
+    }
+
+    /// Copy every mesh the registered objects use so maps can be baked from them on another thread
+    pub fn snapshot(&self, meshes: &AssetMeshes<P, UV>) -> MeshSnapshot<P, UV> {
+        MeshSnapshot::new(meshes, self.handles())
+    }
+
+    /// Like [`WaveObjectRegistry::generate`] but solved, baked and turned into a `Mesh` on the [`AsyncComputeTaskPool`].
+    /// Dropping the task cancels it, objects registered after this is called are not used by it
+    pub fn generate_task(
+        &self,
+        cells: Vec<HexId>,
+        seed: u64,
+        recovery: Recovery,
+        meshes: MeshSnapshot<P, UV>,
+        normals: Option<NormalMode>,
+    ) -> Task<Result<Mesh, GenerateError>> {
+        let rules = self.rules();
+        let objects = self.tiles();
+        let edge = self.edge.clone();
+        AsyncComputeTaskPool::get().spawn(async move {
+            let builder = bake_map(
+                &rules?,
+                &objects,
+                edge.as_ref(),
+                &cells,
+                seed,
+                recovery,
+                &meshes,
+            )?;
+            Ok(extract(&builder, normals))
+        })
+    }
+}
+
+/// Solve `cells` with `rules` and bake `objects`, indexed by tile, into one builder
+fn bake_map<P: VertexPosition + HexTrig, UV: VertexUV>(
+    rules: &AdjacencyRules<6>,
+    objects: &[Arc<MapObject<P, UV>>],
+    edge: Option<&Arc<MapObject<P, UV>>>,
+    cells: &[HexId],
+    seed: u64,
+    recovery: Recovery,
+    meshes: &MapMeshes<P, UV>,
+) -> Result<WaveBuilder<P, UV>, GenerateError> {
+    let graph = HexId::graph(cells);
+    let (collapsed, _) = WaveSolver::new(rules, &graph, seed).solve_with(recovery)?;
+
+    let mut builder = WaveBuilder::new();
+    for (index, cell) in cells.iter().enumerate() {
+        let object = &objects[collapsed.tile(index)];
+        let data = SeededWaveObjects::from_collapsed(
+            &collapsed,
+            &graph,
+            index,
+            objects,
+            edge.unwrap_or(object),
+            cell_seed(seed, *cell),
+        );
+        object.build(cell.world_offset(), meshes, &mut builder, &data)?;
+    }
+    Ok(builder)
+}
+
+/// Ask for a map, once every mesh the registered objects use has loaded the map is solved and baked
+/// and an entity is spawned with its `Handle<Mesh>`, a [`SpatialBundle`] and a [`WaveMap`].
+/// Add a material to the new entity by querying for `Added<WaveMap>`
+#[derive(Debug, Clone)]
+pub struct GenerateMap {
+    pub cells: Vec<HexId>,
+    pub seed: u64,
+    pub transform: Transform,
+    /// How the solver gets out of contradictions in the map
+    pub recovery: Recovery,
+    /// How the normals of the map are worked out, `None` leaves the mesh without normals
+    pub normals: Option<NormalMode>,
+}
+
+impl GenerateMap {
+    /// A map of `cells` with smooth normals at the origin, restarting on contradictions
+    pub fn new(cells: Vec<HexId>, seed: u64) -> Self {
+        GenerateMap {
+            cells,
+            seed,
+            transform: Transform::IDENTITY,
+            recovery: Recovery::default(),
+            normals: Some(NormalMode::default()),
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    pub fn with_normals(mut self, normals: Option<NormalMode>) -> Self {
+        self.normals = normals;
+        self
+    }
+}
+
+/// Sent instead of spawning a map when a [`GenerateMap`] request could not be built
+#[derive(Debug)]
+pub struct MapFailed {
+    pub request: GenerateMap,
+    pub error: GenerateError,
+}
+
+/// On every map spawned for a [`GenerateMap`] request
+#[derive(Component, Debug, Clone)]
+pub struct WaveMap {
+    pub cells: Vec<HexId>,
+    pub seed: u64,
+}
+
+/// The wave meshes objects are baked from and the server loading them
+#[derive(SystemParam)]
+struct WaveMeshes<'w, P: VertexPosition, UV: VertexUV> {
+    meshes: Res<'w, AssetMeshes<P, UV>>,
+    asset_server: Res<'w, AssetServer>,
+}
+
+impl<'w, P: VertexPosition, UV: VertexUV> WaveMeshes<'w, P, UV> {
+    /// True once every handle is loaded or failed to load,
+    /// a mesh missing from a loaded file will never turn up so building reports it as not found
+    fn loaded<'h>(&self, mut handles: impl Iterator<Item = &'h Handle<WaveMesh<P, UV>>>) -> bool {
+        handles.all(|handle| {
+            self.meshes.contains(handle)
+                || matches!(
+                    self.asset_server.get_load_state(handle),
+                    LoadState::Loaded | LoadState::Failed
+                )
+        })
+    }
+}
+
+/// Hold requests until every mesh is loaded or has failed to load, then build them
+fn generate_maps<P: VertexPosition + HexTrig, UV: VertexUV>(
+    mut commands: Commands,
+    mut requests: EventReader<GenerateMap>,
+    mut pending: Local<Vec<GenerateMap>>,
+    mut failed: EventWriter<MapFailed>,
+    registry: Res<WaveObjectRegistry<P, UV>>,
+    wave_meshes: WaveMeshes<P, UV>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    pending.extend(requests.iter().cloned());
+    if pending.is_empty() || !wave_meshes.loaded(registry.handles()) {
+        return;
+    }
+    for request in pending.drain(..) {
+        let builder = match registry.generate(
+            &request.cells,
+            request.seed,
+            request.recovery,
+            &*wave_meshes.meshes,
+        ) {
+            Ok(builder) => builder,
+            Err(error) => {
+                failed.send(MapFailed { request, error });
+                continue;
+            }
+        };
+        commands.spawn((
+            meshes.add(extract(&builder, request.normals)),
+            SpatialBundle::from_transform(request.transform),
+            WaveMap {
+                cells: request.cells,
+                seed: request.seed,
+            },
+        ));
+    }
+}
+
+/// Turn a baked map into a triangle list `Mesh`, with normals when `normals` is set
+fn extract<P: VertexPosition, UV: VertexUV>(
+    builder: &WaveBuilder<P, UV>,
+    normals: Option<NormalMode>,
+) -> Mesh {
+    match normals {
+        Some(mode) => builder.extract_mesh_with_normals(PrimitiveTopology::TriangleList, mode),
+        None => builder.extract_mesh(PrimitiveTopology::TriangleList),
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use super::{extract, WaveMeshes, WaveObjectRegistry};
+use crate::{
+    errors::GenerateError,
+    objects::hexs_map::{ChunkId, ChunkedMap, HexId, HexTrig},
+    prelude::*,
+};
+
+/// Put on the entity chunks are streamed around, such as the camera or the player
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct ChunkFocus;
+
+/// Insert to stream a [`ChunkedMap`] around every [`ChunkFocus`] using the objects in the [`WaveObjectRegistry`].
+/// Each chunk is spawned as its own entity with a `Handle<Mesh>`, a [`SpatialBundle`] at the chunk origin and a [`MapChunk`],
+/// add a material to new chunks by querying for `Added<MapChunk>`.
+/// The map is laid out from the world origin, register every object before inserting this
+#[derive(Resource)]
+pub struct ChunkStreaming {
+    pub map: ChunkedMap,
+    /// Chunks within this many chunk steps of a focus are loaded
+    pub load_radius: u32,
+    /// Loaded chunks further than this from every focus are unloaded,
+    /// keep it above `load_radius` so chunks on the edge are not loaded and unloaded over and over
+    pub unload_radius: u32,
+    /// The most chunks loaded in one frame, solving a chunk can take a while
+    pub chunks_per_frame: usize,
+    /// How the normals of each chunk are worked out, `None` leaves the meshes without normals
+    pub normals: Option<NormalMode>,
+    rules: Option<AdjacencyRules<6>>,
+    loaded: HashMap<ChunkId, (Entity, Handle<Mesh>)>,
+    failed: HashSet<ChunkId>,
+}
+
+impl ChunkStreaming {
+    /// Stream `map` two chunks around each focus, unloading chunks once they are three away
+    pub fn new(map: ChunkedMap) -> Self {
+        ChunkStreaming {
+            map,
+            load_radius: 2,
+            unload_radius: 3,
+            chunks_per_frame: 1,
+            normals: Some(NormalMode::default()),
+            rules: None,
+            loaded: HashMap::new(),
+            failed: HashSet::new(),
+        }
+    }
+
+    pub fn with_radius(mut self, load_radius: u32, unload_radius: u32) -> Self {
+        self.load_radius = load_radius;
+        self.unload_radius = unload_radius.max(load_radius);
+        self
+    }
+
+    pub fn with_chunks_per_frame(mut self, chunks_per_frame: usize) -> Self {
+        self.chunks_per_frame = chunks_per_frame;
+        self
+    }
+
+    pub fn with_normals(mut self, normals: Option<NormalMode>) -> Self {
+        self.normals = normals;
+        self
+    }
+
+    pub fn is_loaded(&self, chunk: ChunkId) -> bool {
+        self.loaded.contains_key(&chunk)
+    }
+
+    /// The entity of every loaded chunk
+    pub fn loaded(&self) -> impl Iterator<Item = (ChunkId, Entity)> + '_ {
+        self.loaded
+            .iter()
+            .map(|(chunk, (entity, _))| (*chunk, *entity))
+    }
+
+    /// Solve `chunk` with the registered objects unless it already is
+    fn solve<P: VertexPosition + HexTrig, UV: VertexUV>(
+        &mut self,
+        chunk: ChunkId,
+        registry: &WaveObjectRegistry<P, UV>,
+    ) -> Result<(), GenerateError> {
+        if self.map.is_solved(chunk) {
+            return Ok(());
+        }
+        if self.rules.is_none() {
+            self.rules = Some(registry.rules()?);
+        }
+        let rules = self.rules.as_ref().expect("rules were just made");
+        self.map.solve(chunk, rules)?;
+        Ok(())
+    }
+}
+
+/// On every chunk entity spawned by [`ChunkStreaming`]
+#[derive(Component, Debug, Clone, Copy)]
+pub struct MapChunk(pub ChunkId);
+
+/// Sent when a chunk could not be solved or baked, it is not tried again
+#[derive(Debug)]
+pub struct ChunkFailed {
+    pub chunk: ChunkId,
+    pub error: GenerateError,
+}
+
+/// Unload chunks that are out of range of every focus and load the closest missing ones,
+/// chunks next to a newly solved chunk are baked again so their edges join up with it
+pub(super) fn stream_chunks<P: VertexPosition + HexTrig, UV: VertexUV>(
+    mut commands: Commands,
+    streaming: Option<ResMut<ChunkStreaming>>,
+    focus: Query<&GlobalTransform, With<ChunkFocus>>,
+    registry: Res<WaveObjectRegistry<P, UV>>,
+    wave_meshes: WaveMeshes<P, UV>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut failed: EventWriter<ChunkFailed>,
+) {
+    let Some(mut streaming) = streaming else {
+        return;
+    };
+    let streaming = &mut *streaming;
+    if registry.is_changed() {
+        streaming.rules = None;
+    }
+    let centers: Vec<ChunkId> = focus
+        .iter()
+        .map(|transform| {
+            let position = transform.translation();
+            streaming
+                .map
+                .chunk(HexId::from_world(position.x, position.z))
+        })
+        .collect();
+    if centers.is_empty() {
+        return;
+    }
+
+    let unload_radius = streaming.unload_radius;
+    streaming.loaded.retain(|chunk, (entity, _)| {
+        let keep = centers
+            .iter()
+            .any(|center| center.0.distance(chunk.0) <= unload_radius);
+        if !keep {
+            commands.entity(*entity).despawn_recursive();
+        }
+        keep
+    });
+
+    if !wave_meshes.loaded(registry.handles()) {
+        return;
+    }
+    let mut seen = HashSet::new();
+    let mut wanted: Vec<ChunkId> = centers
+        .iter()
+        .flat_map(|center| HexId::spiral(center.0, streaming.load_radius).map(ChunkId))
+        .filter(|chunk| {
+            !streaming.loaded.contains_key(chunk)
+                && !streaming.failed.contains(chunk)
+                && seen.insert(*chunk)
+        })
+        .collect();
+    // closest to any focus first
+    wanted.sort_by_key(|chunk| {
+        centers
+            .iter()
+            .map(|center| center.0.distance(chunk.0))
+            .min()
+    });
+
+    let objects = registry.tiles();
+    let edge = registry.edge.as_ref();
+    let wave_meshes: &MapMeshes<P, UV> = &*wave_meshes.meshes;
+    for chunk in wanted.into_iter().take(streaming.chunks_per_frame) {
+        let newly_solved = !streaming.map.is_solved(chunk);
+        let baked = streaming.solve(chunk, &registry).and_then(|_| {
+            streaming
+                .map
+                .bake(chunk, &objects, edge, wave_meshes)
+                .map_err(GenerateError::from)
+        });
+        let builder = match baked {
+            Ok(builder) => builder,
+            Err(error) => {
+                streaming.failed.insert(chunk);
+                failed.send(ChunkFailed { chunk, error });
+                continue;
+            }
+        };
+
+        let handle = meshes.add(extract(&builder, streaming.normals));
+        let [x, y, z] = chunk
+            .origin(streaming.map.size)
+            .world_offset::<P>()
+            .to_f32x3();
+        let entity = commands
+            .spawn((
+                handle.clone(),
+                SpatialBundle::from_transform(Transform::from_xyz(x, y, z)),
+                MapChunk(chunk),
+            ))
+            .id();
+        streaming.loaded.insert(chunk, (entity, handle));
+
+        if !newly_solved {
+            continue;
+        }
+        for neighbour in chunk.neighbours() {
+            let Some((_, handle)) = streaming.loaded.get(&neighbour) else {
+                continue;
+            };
+            match streaming.map.bake(neighbour, &objects, edge, wave_meshes) {
+                Ok(builder) => {
+                    if let Some(mesh) = meshes.get_mut(handle) {
+                        *mesh = extract(&builder, streaming.normals);
+                    }
+                }
+                Err(error) => failed.send(ChunkFailed {
+                    chunk: neighbour,
+                    error: error.into(),
+                }),
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::{prelude::*, tasks::Task};
+use futures_lite::future;
+
+use super::{WaveMap, WaveMeshes, WaveObjectRegistry};
+use crate::{
+    errors::GenerateError,
+    objects::hexs_map::{HexId, HexTrig},
+    prelude::*,
+};
+
+/// Put on an entity to build a map for it on the `AsyncComputeTaskPool` instead of during the frame.
+/// Once every mesh the registered objects use has loaded a [`MapTask`] is added, when it is done both are removed
+/// and the entity gets the map's `Handle<Mesh>` and a [`WaveMap`], spawn it with a [`SpatialBundle`] to see it.
+/// Changing the request starts the map again, removing it or despawning the entity cancels the map
+#[derive(Component, Debug, Clone)]
+pub struct GenerateMapAsync {
+    pub cells: Vec<HexId>,
+    pub seed: u64,
+    /// How the solver gets out of contradictions in the map
+    pub recovery: Recovery,
+    /// How the normals of the map are worked out, `None` leaves the mesh without normals
+    pub normals: Option<NormalMode>,
+}
+
+impl GenerateMapAsync {
+    /// A map of `cells` with smooth normals, restarting on contradictions
+    pub fn new(cells: Vec<HexId>, seed: u64) -> Self {
+        GenerateMapAsync {
+            cells,
+            seed,
+            recovery: Recovery::default(),
+            normals: Some(NormalMode::default()),
+        }
+    }
+
+    pub fn with_recovery(mut self, recovery: Recovery) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    pub fn with_normals(mut self, normals: Option<NormalMode>) -> Self {
+        self.normals = normals;
+        self
+    }
+}
+
+/// The map of a [`GenerateMapAsync`] being built, dropping it cancels the task.
+/// Remove the request to cancel the map, removing only this starts the map again
+#[derive(Component)]
+pub struct MapTask(Task<Result<Mesh, GenerateError>>);
+
+impl MapTask {
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+}
+
+/// Sent when the map of a [`GenerateMapAsync`] could not be built, the request is removed from `entity`
+#[derive(Debug)]
+pub struct MapTaskFailed {
+    pub entity: Entity,
+    pub error: GenerateError,
+}
+
+/// Cancel tasks whose request was removed and start a task for every request without one, or that changed,
+/// once the meshes are loaded. All of them share one snapshot
+pub(super) fn start_map_tasks<P: VertexPosition + HexTrig, UV: VertexUV>(
+    mut commands: Commands,
+    requests: Query<(Entity, Ref<GenerateMapAsync>, Option<&MapTask>)>,
+    cancelled: Query<Entity, (With<MapTask>, Without<GenerateMapAsync>)>,
+    registry: Res<WaveObjectRegistry<P, UV>>,
+    wave_meshes: WaveMeshes<P, UV>,
+) {
+    for entity in &cancelled {
+        commands.entity(entity).remove::<MapTask>();
+    }
+    let mut snapshot = None;
+    for (entity, request, task) in &requests {
+        if task.is_some() && !request.is_changed() {
+            continue;
+        }
+        if snapshot.is_none() && wave_meshes.loaded(registry.handles()) {
+            snapshot = Some(registry.snapshot(&wave_meshes.meshes));
+        }
+        let Some(snapshot) = &snapshot else {
+            // the old task was for the request before it changed, it starts again once the meshes are loaded
+            if task.is_some() {
+                commands.entity(entity).remove::<MapTask>();
+            }
+            continue;
+        };
+        let task = registry.generate_task(
+            request.cells.clone(),
+            request.seed,
+            request.recovery,
+            snapshot.clone(),
+            request.normals,
+        );
+        commands.entity(entity).insert(MapTask(task));
+    }
+}
+
+/// Hand finished maps to their entities, runs after [`start_map_tasks`] so a map is never labelled with a newer request
+pub(super) fn finish_map_tasks(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut MapTask, Ref<GenerateMapAsync>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut failed: EventWriter<MapTaskFailed>,
+) {
+    for (entity, mut task, request) in &mut tasks {
+        if request.is_changed() {
+            continue;
+        }
+        let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<(MapTask, GenerateMapAsync)>();
+        match result {
+            Ok(mesh) => {
+                entity_commands.insert((
+                    meshes.add(mesh),
+                    WaveMap {
+                        cells: request.cells.clone(),
+                        seed: request.seed,
+                    },
+                ));
+            }
+            Err(error) => failed.send(MapTaskFailed { entity, error }),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use rand::Rng;
+
+use super::{AdjacencyRules, TileSet};
+
+const LN_2: f64 = std::f64::consts::LN_2;
+
+/// Natural log built only from `+ - * /` so every platform gets the same bits back,
+/// `f64::ln` is left to the platform's libm which is free to round differently
+pub(crate) fn ln(x: f64) -> f64 {
+    if x <= 0. || !x.is_finite() {
+        return if x == 0. { f64::NEG_INFINITY } else { f64::NAN };
+    }
+    let mut bits = x.to_bits();
+    let mut exponent = ((bits >> 52) & 0x7ff) as i64;
+    if exponent == 0 {
+        // subnormal, scale into the normal range first
+        bits = (x * (1u64 << 54) as f64).to_bits();
+        exponent = ((bits >> 52) & 0x7ff) as i64 - 54;
+    }
+    let exponent = exponent - 1023;
+    // mantissa in [1, 2)
+    let mantissa = f64::from_bits((bits & 0x000f_ffff_ffff_ffff) | 0x3ff0_0000_0000_0000);
+    // ln(m) = 2 * atanh((m - 1) / (m + 1)), t is at most 1/3 so the series converges quickly
+    let t = (mantissa - 1.) / (mantissa + 1.);
+    let t2 = t * t;
+    let mut term = t;
+    let mut sum = 0.;
+    let mut divisor = 1.;
+    while divisor < 40. {
+        sum += term / divisor;
+        term *= t2;
+        divisor += 2.;
+    }
+    exponent as f64 * LN_2 + 2. * sum
+}
+
+/// The weights of every tile with `w * ln(w)` cached for the entropy sum
+pub(crate) struct EntropyTable {
+    weights: Vec<f64>,
+    weight_log_weights: Vec<f64>,
+}
+
+impl EntropyTable {
+    pub(crate) fn new<const N: usize>(rules: &AdjacencyRules<N>) -> EntropyTable {
+        let weights: Vec<f64> = (0..rules.len()).map(|i| rules.weight(i) as f64).collect();
+        let weight_log_weights = weights
+            .iter()
+            .map(|w| if *w > 0. { w * ln(*w) } else { 0. })
+            .collect();
+        EntropyTable {
+            weights,
+            weight_log_weights,
+        }
+    }
+
+    /// Shannon entropy of picking a tile from `set` with the tile weights as probabilities
+    pub(crate) fn entropy(&self, set: &TileSet) -> f64 {
+        let mut sum = 0.;
+        let mut sum_log = 0.;
+        for tile in set.iter() {
+            sum += self.weights[tile];
+            sum_log += self.weight_log_weights[tile];
+        }
+        if sum <= 0. {
+            return 0.;
+        }
+        ln(sum) - sum_log / sum
+    }
+
+    /// Pick a tile from `set` with the chance of each tile proportional to its weight,
+    /// if every tile has no weight they are all equally likely
+    pub(crate) fn pick(&self, set: &TileSet, rng: &mut impl Rng) -> Option<usize> {
+        let total: f64 = set.iter().map(|tile| self.weights[tile]).sum();
+        if total <= 0. {
+            let count = set.count();
+            if count == 0 {
+                return None;
+            }
+            return set.iter().nth(rng.gen_range(0..count as u64) as usize);
+        }
+        let mut pick = rng.gen::<f64>() * total;
+        let mut last = None;
+        for tile in set.iter() {
+            let weight = self.weights[tile];
+            if weight <= 0. {
+                continue;
+            }
+            if pick < weight {
+                return Some(tile);
+            }
+            pick -= weight;
+            last = Some(tile);
+        }
+        // rounding can leave a tiny bit of pick over, that belongs to the last tile
+        last
+    }
+}
@@ -0,0 +1,106 @@
+/// The cells of a map and which cell sits on each of their `N` sides
+///
+/// Directions are expected to be laid out so that the opposite of direction `d` is `(d + N / 2) % N`,
+/// this is true for the hex rotations in `HexTrig` where direction `d` is rotated by `d * 60` degrees
+#[derive(Clone, Debug)]
+pub struct CellGraph<const N: usize> {
+    neighbours: Vec<[Option<usize>; N]>,
+}
+
+impl<const N: usize> Default for CellGraph<N> {
+    fn default() -> Self {
+        CellGraph::new()
+    }
+}
+
+impl<const N: usize> CellGraph<N> {
+    pub fn new() -> CellGraph<N> {
+        CellGraph {
+            neighbours: Vec::new(),
+        }
+    }
+
+    /// A graph of `count` cells with no connections between them
+    pub fn with_cells(count: usize) -> CellGraph<N> {
+        CellGraph {
+            neighbours: vec![[None; N]; count],
+        }
+    }
+
+    /// Build a graph from a list of cell coordinates, cell `i` in the graph is `cells[i]`.
+    /// `neighbour` gives the coordinate on the `direction` side of a cell
+    pub fn from_cells<T: std::hash::Hash + Eq>(
+        cells: &[T],
+        neighbour: impl Fn(&T, usize) -> T,
+    ) -> CellGraph<N> {
+        let lookup: std::collections::HashMap<&T, usize> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| (cell, i))
+            .collect();
+        let mut graph = CellGraph::with_cells(cells.len());
+        for (index, cell) in cells.iter().enumerate() {
+            // `connect` fills in the opposite side so only half the directions need checking
+            for direction in 0..N / 2 {
+                if let Some(other) = lookup.get(&neighbour(cell, direction)) {
+                    graph.connect(index, direction, *other);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Add a new unconnected cell returning its index
+    pub fn add_cell(&mut self) -> usize {
+        self.neighbours.push([None; N]);
+        self.neighbours.len() - 1
+    }
+
+    /// Connect `other` to the `direction` side of `cell`,
+    /// `cell` is also connected to the opposite side of `other`
+    pub fn connect(&mut self, cell: usize, direction: usize, other: usize) {
+        self.neighbours[cell][direction] = Some(other);
+        self.neighbours[other][Self::opposite(direction)] = Some(cell);
+    }
+
+    /// The cell on the `direction` side of `cell` if there is one
+    pub fn neighbour(&self, cell: usize, direction: usize) -> Option<usize> {
+        self.neighbours[cell][direction]
+    }
+
+    pub fn neighbours(&self, cell: usize) -> &[Option<usize>; N] {
+        &self.neighbours[cell]
+    }
+
+    /// Every cell at most `radius` steps from `cell`, including `cell` itself
+    pub fn within(&self, cell: usize, radius: usize) -> Vec<usize> {
+        let mut found = vec![cell];
+        let mut seen = std::collections::HashSet::from([cell]);
+        let mut start = 0;
+        for _ in 0..radius {
+            let end = found.len();
+            for i in start..end {
+                for neighbour in self.neighbours[found[i]].iter().flatten() {
+                    if seen.insert(*neighbour) {
+                        found.push(*neighbour);
+                    }
+                }
+            }
+            start = end;
+        }
+        found
+    }
+
+    /// The direction that points back the way `direction` came from
+    pub const fn opposite(direction: usize) -> usize {
+        (direction + N / 2) % N
+    }
+
+    pub fn len(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.neighbours.is_empty()
+    }
+}
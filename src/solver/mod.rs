@@ -0,0 +1,276 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{errors::CollapseError, objects::Connection};
+
+mod entropy;
+mod graph;
+mod overlapping;
+mod recovery;
+mod rules;
+mod tile_set;
+
+use entropy::EntropyTable;
+use recovery::Trail;
+
+pub use graph::CellGraph;
+pub use overlapping::OverlappingModel;
+pub use recovery::{CollapseStats, Recovery};
+pub use rules::AdjacencyRules;
+pub use tile_set::TileSet;
+
+/// Picks a tile for every cell of a [`CellGraph`] so that every pair of neighbours is allowed by the [`AdjacencyRules`]
+pub struct WaveSolver<'a, const N: usize> {
+    rules: &'a AdjacencyRules<N>,
+    graph: &'a CellGraph<N>,
+    cells: Vec<TileSet>,
+    /// cached entropy of each cell, None when the cell has changed since it was last worked out
+    entropies: Vec<Option<f64>>,
+    entropy: EntropyTable,
+    pending: Vec<usize>,
+    seed: u64,
+    rng: StdRng,
+    /// set while backtracking so observations can be undone
+    trail: Option<Trail>,
+}
+
+impl<'a, const N: usize> WaveSolver<'a, N> {
+    /// Start a solver with every tile possible in every cell,
+    /// the same rules, graph and seed always give the same result on every platform
+    pub fn new(rules: &'a AdjacencyRules<N>, graph: &'a CellGraph<N>, seed: u64) -> Self {
+        WaveSolver {
+            rules,
+            graph,
+            cells: vec![TileSet::full(rules.len()); graph.len()],
+            entropies: vec![None; graph.len()],
+            entropy: EntropyTable::new(rules),
+            pending: Vec::new(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            trail: None,
+        }
+    }
+
+    /// Remove every tile not in `tiles` from `cell` and propagate the change to the rest of the graph
+    pub fn constrain(&mut self, cell: usize, tiles: &TileSet) -> Result<(), CollapseError> {
+        let candidates = self
+            .cells
+            .get_mut(cell)
+            .ok_or(CollapseError::UnknownCell(cell))?;
+        if candidates.intersect_with(tiles) {
+            if candidates.is_empty() {
+                return Err(CollapseError::Contradiction { cell });
+            }
+            self.entropies[cell] = None;
+            self.pending.push(cell);
+        }
+        self.propagate()
+    }
+
+    /// Force `cell` to be `tile` and propagate the change to the rest of the graph
+    pub fn set(&mut self, cell: usize, tile: &Connection) -> Result<(), CollapseError> {
+        let index = self
+            .rules
+            .index(tile)
+            .ok_or_else(|| CollapseError::UnknownTile(tile.name().to_string()))?;
+        let mut tiles = TileSet::empty(self.rules.len());
+        tiles.insert(index);
+        self.constrain(cell, &tiles)
+    }
+
+    /// The tiles that are still possible for `cell`
+    pub fn candidates(&self, cell: usize) -> &TileSet {
+        &self.cells[cell]
+    }
+
+    /// True once every cell has exactly one tile left
+    pub fn is_collapsed(&self) -> bool {
+        self.cells.iter().all(|cell| cell.count() == 1)
+    }
+
+    /// Collapse the undecided cell with the lowest entropy to a single tile picked by weight,
+    /// returns the cell that was collapsed or None if every cell is already decided
+    pub fn observe(&mut self) -> Option<usize> {
+        let mut lowest = f64::INFINITY;
+        let mut ties = 0;
+        let mut chosen = None;
+        for (cell, candidates) in self.cells.iter().enumerate() {
+            if candidates.count() <= 1 {
+                continue;
+            }
+            let entropy =
+                *self.entropies[cell].get_or_insert_with(|| self.entropy.entropy(candidates));
+            if entropy > lowest {
+                continue;
+            }
+            if entropy < lowest {
+                lowest = entropy;
+                ties = 0;
+            }
+            // reservoir sample so ties are broken by the seed rather than the cell order,
+            // sampled as u64 since rand picks from usize ranges differently on 32 and 64 bit targets
+            ties += 1;
+            if self.rng.gen_range(0..ties as u64) == 0 {
+                chosen = Some(cell);
+            }
+        }
+        let cell = chosen?;
+        let tile = self
+            .entropy
+            .pick(&self.cells[cell], &mut self.rng)
+            .expect("cell has more then one candidate");
+        let candidates = &mut self.cells[cell];
+        if let Some(trail) = &mut self.trail {
+            trail.record(cell, candidates);
+        }
+        candidates.clear();
+        candidates.insert(tile);
+        self.entropies[cell] = None;
+        self.pending.push(cell);
+        Some(cell)
+    }
+
+    /// Remove candidates that are no longer allowed by any candidate of a neighbouring cell,
+    /// until no more can be removed
+    pub fn propagate(&mut self) -> Result<(), CollapseError> {
+        let mut allowed = TileSet::empty(self.rules.len());
+        while let Some(cell) = self.pending.pop() {
+            for direction in 0..N {
+                let Some(neighbour) = self.graph.neighbour(cell, direction) else {
+                    continue;
+                };
+                allowed.clear();
+                for tile in self.cells[cell].iter() {
+                    allowed.union_with(self.rules.allowed(tile, direction));
+                }
+                let candidates = &mut self.cells[neighbour];
+                if let Some(trail) = &mut self.trail {
+                    if !candidates.is_subset(&allowed) {
+                        trail.record(neighbour, candidates);
+                    }
+                }
+                if candidates.intersect_with(&allowed) {
+                    if candidates.is_empty() {
+                        self.pending.clear();
+                        return Err(CollapseError::Contradiction { cell: neighbour });
+                    }
+                    self.entropies[neighbour] = None;
+                    self.pending.push(neighbour);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a single observe and propagate, returns true once every cell is collapsed
+    pub fn step(&mut self) -> Result<bool, CollapseError> {
+        if self.observe().is_none() {
+            return Ok(true);
+        }
+        self.propagate()?;
+        Ok(false)
+    }
+
+    /// Observe and propagate until every cell is collapsed, failing on the first contradiction,
+    /// see [`WaveSolver::solve_with`] to recover from them instead
+    pub fn solve(mut self) -> Result<Collapsed, CollapseError> {
+        self.propagate()?;
+        if let Some(cell) = self.cells.iter().position(|cell| cell.is_empty()) {
+            return Err(CollapseError::Contradiction { cell });
+        }
+        while !self.step()? {}
+        Ok(self.collapsed())
+    }
+
+    fn collapsed(&self) -> Collapsed {
+        Collapsed {
+            tiles: self
+                .cells
+                .iter()
+                .map(|cell| cell.iter().next().expect("cell is collapsed"))
+                .collect(),
+        }
+    }
+}
+
+/// The tile index chosen for each cell of a solved [`CellGraph`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collapsed {
+    tiles: Vec<usize>,
+}
+
+impl Collapsed {
+    /// The index of the tile chosen for `cell`, this is the same index used by [`AdjacencyRules`]
+    pub fn tile(&self, cell: usize) -> usize {
+        self.tiles[cell]
+    }
+
+    /// The name of the tile chosen for `cell`
+    pub fn connection<'r, const N: usize>(
+        &self,
+        rules: &'r AdjacencyRules<N>,
+        cell: usize,
+    ) -> &'r Connection {
+        rules.tile(self.tiles[cell])
+    }
+
+    pub fn tiles(&self) -> &[usize] {
+        &self.tiles
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `len` cells in a row, direction 0 is the next cell and 1 the one before
+    fn line(len: usize) -> CellGraph<2> {
+        let mut graph = CellGraph::with_cells(len);
+        for cell in 1..len {
+            graph.connect(cell - 1, 0, cell);
+        }
+        graph
+    }
+
+    #[test]
+    fn solves_alternating_line() {
+        let mut rules = AdjacencyRules::<2>::new(["A", "B"]);
+        rules.allow_all(&"A".into(), &"B".into()).unwrap();
+        let graph = line(5);
+        let mut solver = WaveSolver::new(&rules, &graph, 0);
+        solver.set(0, &"A".into()).unwrap();
+        let collapsed = solver.solve().unwrap();
+        assert_eq!(collapsed.tiles(), &[0, 1, 0, 1, 0]);
+        assert_eq!(collapsed.connection(&rules, 1), &Connection::from("B"));
+    }
+
+    #[test]
+    fn reports_contradiction() {
+        // A and B may only sit next to each other, so three cells in a ring can not be solved
+        let mut rules = AdjacencyRules::<2>::new(["A", "B"]);
+        rules.allow_all(&"A".into(), &"B".into()).unwrap();
+        let mut graph = line(3);
+        graph.connect(2, 0, 0);
+        let result = WaveSolver::new(&rules, &graph, 0).solve();
+        assert!(matches!(result, Err(CollapseError::Contradiction { .. })));
+    }
+
+    #[test]
+    fn same_seed_same_result() {
+        let mut rules = AdjacencyRules::<2>::new(["A", "B", "C"]);
+        for (tile, neighbour) in [("A", "A"), ("A", "B"), ("B", "C"), ("C", "C")] {
+            rules.allow_all(&tile.into(), &neighbour.into()).unwrap();
+        }
+        let graph = line(64);
+        let first = WaveSolver::new(&rules, &graph, 42).solve().unwrap();
+        let second = WaveSolver::new(&rules, &graph, 42).solve().unwrap();
+        assert_eq!(first, second);
+    }
+}
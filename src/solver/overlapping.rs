@@ -0,0 +1,301 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    ops::{Add, Sub},
+};
+
+use super::{AdjacencyRules, Collapsed};
+use crate::{
+    errors::{LearnError, ParseModelError},
+    objects::Connection,
+};
+
+/// Adjacency rules and weights learned from an example map instead of written by hand.
+///
+/// Every place a window of cells fits inside the example becomes a pattern, two patterns may sit
+/// next to each other when they agree on every cell where their windows overlap.
+/// The solver places patterns, [`OverlappingModel::tile`] turns a pattern back into the example tile at its anchor
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlappingModel<const N: usize> {
+    tiles: Vec<Connection>,
+    patterns: Vec<Pattern>,
+    /// `allowed[pattern][direction]` are the patterns that may sit on the `direction` side of `pattern`
+    allowed: Vec<[Vec<usize>; N]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pattern {
+    /// the example tile at the origin of the window
+    tile: usize,
+    /// how many times the pattern was seen in the example
+    count: usize,
+}
+
+impl<const N: usize> OverlappingModel<N> {
+    /// Learn patterns from `example`, a list of cells and the tile painted in each.
+    /// `window` is the shape of a pattern as offsets from its anchor and must contain the origin (`C::default()`),
+    /// use something like `SquareId::rect(3, 3)` or `HexId::spiral(HexId::ZERO, 1)`.
+    /// The window should reach the neighbours of the anchor or nothing will constrain the patterns.
+    /// `neighbour` is the same function given to [`super::CellGraph::from_cells`]
+    pub fn learn<C>(
+        example: impl IntoIterator<Item = (C, Connection)>,
+        window: &[C],
+        neighbour: impl Fn(&C, usize) -> C,
+    ) -> Result<OverlappingModel<N>, LearnError>
+    where
+        C: Copy + Eq + Hash + Default + Add<Output = C> + Sub<Output = C>,
+    {
+        let origin = C::default();
+        let anchor = window
+            .iter()
+            .position(|offset| *offset == origin)
+            .ok_or(LearnError::NoAnchor)?;
+
+        let mut tiles = Vec::new();
+        let mut tile_lookup = HashMap::new();
+        let mut map = HashMap::new();
+        // kept in the order they were given so the same example always learns the same model
+        let mut cells = Vec::new();
+        for (cell, tile) in example {
+            let index = *tile_lookup.entry(tile.clone()).or_insert_with(|| {
+                tiles.push(tile);
+                tiles.len() - 1
+            });
+            if map.insert(cell, index).is_none() {
+                cells.push(cell);
+            }
+        }
+
+        let mut windows: Vec<Vec<usize>> = Vec::new();
+        let mut patterns: Vec<Pattern> = Vec::new();
+        let mut pattern_lookup: HashMap<Vec<usize>, usize> = HashMap::new();
+        for cell in cells {
+            let Some(found) = window
+                .iter()
+                .map(|offset| map.get(&(cell + *offset)).copied())
+                .collect::<Option<Vec<usize>>>()
+            else {
+                continue;
+            };
+            match pattern_lookup.get(&found) {
+                Some(index) => patterns[*index].count += 1,
+                None => {
+                    pattern_lookup.insert(found.clone(), patterns.len());
+                    patterns.push(Pattern {
+                        tile: found[anchor],
+                        count: 1,
+                    });
+                    windows.push(found);
+                }
+            }
+        }
+        if patterns.is_empty() {
+            return Err(LearnError::NoPatterns);
+        }
+
+        // the pairs of window cells that land on the same map cell when the second window is one step in `direction`
+        let overlaps: [Vec<(usize, usize)>; N] = std::array::from_fn(|direction| {
+            let step = neighbour(&origin, direction);
+            window
+                .iter()
+                .enumerate()
+                .filter_map(|(i, offset)| {
+                    window
+                        .iter()
+                        .position(|other| *other == *offset - step)
+                        .map(|j| (i, j))
+                })
+                .collect()
+        });
+
+        let allowed = windows
+            .iter()
+            .map(|pattern| {
+                std::array::from_fn(|direction| {
+                    windows
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, other)| {
+                            overlaps[direction]
+                                .iter()
+                                .all(|(i, j)| pattern[*i] == other[*j])
+                        })
+                        .map(|(index, _)| index)
+                        .collect()
+                })
+            })
+            .collect();
+
+        Ok(OverlappingModel {
+            tiles,
+            patterns,
+            allowed,
+        })
+    }
+
+    /// Rules with one tile per pattern weighted by how often it was seen,
+    /// tile `i` of the rules is pattern `i` of the model
+    pub fn rules(&self) -> AdjacencyRules<N> {
+        let mut rules = AdjacencyRules::new(std::iter::empty::<Connection>());
+        for (index, pattern) in self.patterns.iter().enumerate() {
+            rules.add_weighted_tile(
+                Connection::new(format!("{}#{}", self.tiles[pattern.tile].name(), index)),
+                pattern.count as f32,
+            );
+        }
+        for (pattern, sides) in self.allowed.iter().enumerate() {
+            for (direction, allowed) in sides.iter().enumerate() {
+                for other in allowed.iter() {
+                    rules.allow_index(pattern, direction, *other);
+                }
+            }
+        }
+        rules
+    }
+
+    /// The example tile at the anchor of `pattern`
+    pub fn tile(&self, pattern: usize) -> &Connection {
+        &self.tiles[self.patterns[pattern].tile]
+    }
+
+    /// The example tile chosen for every cell of a map solved with [`OverlappingModel::rules`]
+    pub fn resolve(&self, collapsed: &Collapsed) -> Vec<&Connection> {
+        collapsed
+            .tiles()
+            .iter()
+            .map(|pattern| self.tile(*pattern))
+            .collect()
+    }
+
+    /// How many times `pattern` was seen in the example
+    pub fn count(&self, pattern: usize) -> usize {
+        self.patterns[pattern].count
+    }
+
+    /// The different tiles found in the example
+    pub fn tiles(&self) -> &[Connection] {
+        &self.tiles
+    }
+
+    /// The number of patterns
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Write the model out as text so it can be loaded with [`OverlappingModel::load`] instead of learned again
+    pub fn save(&self) -> String {
+        let mut data = format!("overlapping {}\ntiles {}\n", N, self.tiles.len());
+        for tile in self.tiles.iter() {
+            data.push_str(tile.name());
+            data.push('\n');
+        }
+        data.push_str(&format!("patterns {}\n", self.patterns.len()));
+        for (pattern, sides) in self.patterns.iter().zip(self.allowed.iter()) {
+            data.push_str(&format!("pattern {} {}\n", pattern.tile, pattern.count));
+            for allowed in sides.iter() {
+                data.push_str("allow");
+                for other in allowed.iter() {
+                    data.push_str(&format!(" {}", other));
+                }
+                data.push('\n');
+            }
+        }
+        data
+    }
+
+    /// Read a model written by [`OverlappingModel::save`]
+    pub fn load(data: &str) -> Result<OverlappingModel<N>, ParseModelError> {
+        let mut lines = data
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line));
+
+        let (_, directions) = header(&mut lines, "overlapping")?;
+        if directions != N {
+            return Err(ParseModelError::WrongDirections {
+                expected: N,
+                found: directions,
+            });
+        }
+
+        let (_, tile_count) = header(&mut lines, "tiles")?;
+        let mut tiles = Vec::with_capacity(tile_count);
+        for _ in 0..tile_count {
+            let (_, name) = lines
+                .next()
+                .ok_or(ParseModelError::UnexpectedEnd("tile name"))?;
+            tiles.push(Connection::new(name.to_string()));
+        }
+
+        let (_, pattern_count) = header(&mut lines, "patterns")?;
+        let mut patterns = Vec::with_capacity(pattern_count);
+        let mut allowed = Vec::with_capacity(pattern_count);
+        for _ in 0..pattern_count {
+            let (line, values) = numbers(&mut lines, "pattern")?;
+            let [tile, count] = values[..] else {
+                return Err(ParseModelError::ExpectedSymbol {
+                    expect: "pattern tile count",
+                    line,
+                });
+            };
+            if tile >= tiles.len() {
+                return Err(ParseModelError::OutOfRange { index: tile, line });
+            }
+            patterns.push(Pattern { tile, count });
+
+            let mut sides: [Vec<usize>; N] = std::array::from_fn(|_| Vec::new());
+            for side in sides.iter_mut() {
+                let (line, values) = numbers(&mut lines, "allow")?;
+                if let Some(index) = values.iter().find(|index| **index >= pattern_count) {
+                    return Err(ParseModelError::OutOfRange {
+                        index: *index,
+                        line,
+                    });
+                }
+                *side = values;
+            }
+            allowed.push(sides);
+        }
+
+        Ok(OverlappingModel {
+            tiles,
+            patterns,
+            allowed,
+        })
+    }
+}
+
+/// Read a line like `tiles 4` returning the number
+fn header<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    expect: &'static str,
+) -> Result<(usize, usize), ParseModelError> {
+    let (line, values) = numbers(lines, expect)?;
+    match values[..] {
+        [value] => Ok((line, value)),
+        _ => Err(ParseModelError::ExpectedSymbol { expect, line }),
+    }
+}
+
+/// Read a line starting with `expect` followed by any number of unsigned ints
+fn numbers<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    expect: &'static str,
+) -> Result<(usize, Vec<usize>), ParseModelError> {
+    let (line, text) = lines.next().ok_or(ParseModelError::UnexpectedEnd(expect))?;
+    let mut words = text.split_whitespace();
+    if words.next() != Some(expect) {
+        return Err(ParseModelError::ExpectedSymbol { expect, line });
+    }
+    let values = words
+        .map(|word| {
+            word.parse()
+                .map_err(|e| ParseModelError::FailedToParseInt(e, line))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+    Ok((line, values))
+}
@@ -0,0 +1,333 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::{Collapsed, TileSet, WaveSolver};
+use crate::errors::{CollapseError, RecoveryError};
+
+/// What the solver does when propagation leaves a cell with no tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Throw everything away and start again with a new seed derived from the last one
+    Restart { max_restarts: usize },
+    /// Undo the last observation and ban the tile it picked,
+    /// only the last `max_depth` observations are remembered
+    Backtrack {
+        max_depth: usize,
+        max_backtracks: usize,
+    },
+    /// Reset every cell within `radius` steps of the contradiction and carry on,
+    /// the radius grows by one each time it fails again before the next observation
+    Unsolve { radius: usize, max_attempts: usize },
+}
+
+impl Default for Recovery {
+    fn default() -> Self {
+        Recovery::Restart { max_restarts: 10 }
+    }
+}
+
+/// How much work it took to recover from contradictions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollapseStats {
+    pub restarts: usize,
+    pub backtracks: usize,
+    /// Cells reset by [`Recovery::Unsolve`] and solved again
+    pub cells_resolved: usize,
+}
+
+impl CollapseStats {
+    fn exhausted(&self, cell: usize) -> RecoveryError {
+        RecoveryError::BudgetExhausted {
+            cell,
+            restarts: self.restarts,
+            backtracks: self.backtracks,
+            cells_resolved: self.cells_resolved,
+        }
+    }
+}
+
+/// An observation [`Recovery::Backtrack`] can undo, `mark` is how long the trail was before it
+struct Snapshot {
+    mark: usize,
+    cell: usize,
+    tile: usize,
+}
+
+/// The candidates cells had before each change while backtracking,
+/// so undoing an observation only touches the cells it changed
+#[derive(Default)]
+pub(super) struct Trail {
+    changes: VecDeque<(usize, TileSet)>,
+    /// how many of the oldest changes were forgotten
+    forgotten: usize,
+}
+
+impl Trail {
+    pub(super) fn record(&mut self, cell: usize, candidates: &TileSet) {
+        self.changes.push_back((cell, candidates.clone()));
+    }
+
+    fn mark(&self) -> usize {
+        self.forgotten + self.changes.len()
+    }
+
+    /// Drop the changes made before `mark`, they can no longer be undone
+    fn forget(&mut self, mark: usize) {
+        while self.forgotten < mark {
+            self.changes.pop_front();
+            self.forgotten += 1;
+        }
+    }
+}
+
+/// splitmix64, spreads the bits of the old seed so restarts do not walk through similar seeds
+fn next_seed(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl<'a, const N: usize> WaveSolver<'a, N> {
+    /// Observe and propagate until every cell is collapsed, using `recovery` to get out of contradictions.
+    /// Contradictions caused by [`WaveSolver::constrain`] or [`WaveSolver::set`] before solving can not be recovered from
+    pub fn solve_with(
+        mut self,
+        recovery: Recovery,
+    ) -> Result<(Collapsed, CollapseStats), RecoveryError> {
+        self.propagate()?;
+        if let Some(cell) = self.cells.iter().position(|cell| cell.is_empty()) {
+            return Err(CollapseError::Contradiction { cell }.into());
+        }
+        let initial = self.cells.clone();
+        let mut stats = CollapseStats::default();
+        let mut history = VecDeque::new();
+        let mut unsolve_attempts = 0;
+        let mut streak = 0;
+        if let Recovery::Backtrack { .. } = recovery {
+            self.trail = Some(Trail::default());
+        }
+        loop {
+            let mark = self.trail.as_ref().map_or(0, Trail::mark);
+            let Some(cell) = self.observe() else {
+                break;
+            };
+            if let (Some(trail), Recovery::Backtrack { max_depth, .. }) =
+                (&mut self.trail, recovery)
+            {
+                let tile = self.cells[cell]
+                    .iter()
+                    .next()
+                    .expect("cell was just collapsed");
+                history.push_back(Snapshot { mark, cell, tile });
+                while history.len() > max_depth {
+                    history.pop_front();
+                }
+                trail.forget(
+                    history
+                        .front()
+                        .map_or(trail.mark(), |snapshot| snapshot.mark),
+                );
+            }
+            let mut result = self.propagate();
+            if result.is_ok() {
+                streak = 0;
+            }
+            while let Err(CollapseError::Contradiction { cell }) = result {
+                result = match recovery {
+                    Recovery::Restart { max_restarts } => {
+                        if stats.restarts >= max_restarts {
+                            return Err(stats.exhausted(cell));
+                        }
+                        stats.restarts += 1;
+                        self.seed = next_seed(self.seed);
+                        self.rng = StdRng::seed_from_u64(self.seed);
+                        self.cells.clone_from(&initial);
+                        self.entropies.iter_mut().for_each(|e| *e = None);
+                        Ok(())
+                    }
+                    Recovery::Backtrack { max_backtracks, .. } => {
+                        if stats.backtracks >= max_backtracks {
+                            return Err(stats.exhausted(cell));
+                        }
+                        let Some(snapshot) = history.pop_back() else {
+                            return Err(stats.exhausted(cell));
+                        };
+                        stats.backtracks += 1;
+                        self.undo(snapshot.mark);
+                        let candidates = &mut self.cells[snapshot.cell];
+                        if let Some(trail) = &mut self.trail {
+                            trail.record(snapshot.cell, candidates);
+                        }
+                        candidates.remove(snapshot.tile);
+                        if self.cells[snapshot.cell].is_empty() {
+                            Err(CollapseError::Contradiction {
+                                cell: snapshot.cell,
+                            })
+                        } else {
+                            self.pending.push(snapshot.cell);
+                            self.propagate()
+                        }
+                    }
+                    Recovery::Unsolve {
+                        radius,
+                        max_attempts,
+                    } => {
+                        if unsolve_attempts >= max_attempts {
+                            return Err(stats.exhausted(cell));
+                        }
+                        unsolve_attempts += 1;
+                        let region = self.graph.within(cell, radius + streak);
+                        streak += 1;
+                        stats.cells_resolved += region.len();
+                        self.unsolve(&region, &initial);
+                        self.propagate()
+                    }
+                };
+            }
+            result?;
+        }
+        Ok((self.collapsed(), stats))
+    }
+
+    /// Put every cell changed since the trail was `mark` long back to how it was
+    fn undo(&mut self, mark: usize) {
+        let Some(trail) = &mut self.trail else {
+            return;
+        };
+        while trail.mark() > mark {
+            let (cell, candidates) = trail.changes.pop_back().expect("mark is inside the trail");
+            self.cells[cell] = candidates;
+            self.entropies[cell] = None;
+        }
+    }
+
+    /// Put `region` back to how it was before solving and queue the cells around it
+    /// so the rest of the map constrains it again
+    fn unsolve(&mut self, region: &[usize], initial: &[TileSet]) {
+        for cell in region.iter() {
+            self.cells[*cell].clone_from(&initial[*cell]);
+            self.entropies[*cell] = None;
+            self.pending.push(*cell);
+        }
+        for cell in region.iter() {
+            for neighbour in self.graph.neighbours(*cell).iter().flatten() {
+                if !region.contains(neighbour) {
+                    self.pending.push(*neighbour);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{AdjacencyRules, CellGraph};
+
+    /// `len` cells in a loop, direction 0 is the next cell and 1 the one before
+    fn ring(len: usize) -> CellGraph<2> {
+        let mut graph = CellGraph::with_cells(len);
+        for cell in 0..len {
+            graph.connect(cell, 0, (cell + 1) % len);
+        }
+        graph
+    }
+
+    /// Three cells that must all differ, D only fits between two As so picking it always fails
+    fn trap() -> AdjacencyRules<2> {
+        let mut rules = AdjacencyRules::new(["A", "B", "C", "D"]);
+        for (tile, neighbour) in [("A", "B"), ("B", "C"), ("C", "A"), ("D", "A")] {
+            rules.allow_all(&tile.into(), &neighbour.into()).unwrap();
+        }
+        rules
+    }
+
+    /// Two tiles that must alternate, which a loop of three cells never can
+    fn unsolvable() -> AdjacencyRules<2> {
+        let mut rules = AdjacencyRules::new(["A", "B"]);
+        rules.allow_all(&"A".into(), &"B".into()).unwrap();
+        rules
+    }
+
+    fn assert_valid(rules: &AdjacencyRules<2>, graph: &CellGraph<2>, collapsed: &Collapsed) {
+        for cell in 0..graph.len() {
+            let next = graph.neighbour(cell, 0).unwrap();
+            let allowed = rules.allowed(collapsed.tile(cell), 0);
+            assert!(allowed.contains(collapsed.tile(next)));
+        }
+    }
+
+    /// Solve the trap with `recovery` for every seed until one needed to recover
+    fn recovers(recovery: Recovery, worked: impl Fn(&CollapseStats) -> bool) {
+        let rules = trap();
+        let graph = ring(3);
+        let recovered = (0..64).any(|seed| {
+            let (collapsed, stats) = WaveSolver::new(&rules, &graph, seed)
+                .solve_with(recovery)
+                .unwrap();
+            assert_valid(&rules, &graph, &collapsed);
+            worked(&stats)
+        });
+        assert!(recovered);
+    }
+
+    fn exhausted(recovery: Recovery) -> CollapseStats {
+        let rules = unsolvable();
+        let graph = ring(3);
+        match WaveSolver::new(&rules, &graph, 0).solve_with(recovery) {
+            Err(RecoveryError::BudgetExhausted {
+                restarts,
+                backtracks,
+                cells_resolved,
+                ..
+            }) => CollapseStats {
+                restarts,
+                backtracks,
+                cells_resolved,
+            },
+            other => panic!(
+                "expected the budget to run out, got {:?}",
+                other.map(|(_, s)| s)
+            ),
+        }
+    }
+
+    #[test]
+    fn restart() {
+        recovers(Recovery::Restart { max_restarts: 32 }, |stats| {
+            stats.restarts > 0
+        });
+        let stats = exhausted(Recovery::Restart { max_restarts: 3 });
+        assert_eq!(stats.restarts, 3);
+    }
+
+    #[test]
+    fn backtrack() {
+        let recovery = Recovery::Backtrack {
+            max_depth: 4,
+            max_backtracks: 32,
+        };
+        recovers(recovery, |stats| stats.backtracks > 0);
+        let stats = exhausted(Recovery::Backtrack {
+            max_depth: 4,
+            max_backtracks: 1,
+        });
+        assert_eq!(stats.backtracks, 1);
+    }
+
+    #[test]
+    fn unsolve() {
+        let recovery = Recovery::Unsolve {
+            radius: 1,
+            max_attempts: 32,
+        };
+        recovers(recovery, |stats| stats.cells_resolved > 0);
+        let stats = exhausted(Recovery::Unsolve {
+            radius: 1,
+            max_attempts: 2,
+        });
+        assert!(stats.cells_resolved > 0);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use super::{CellGraph, TileSet};
+use crate::{
+    errors::CollapseError,
+    objects::{Connection, Socket},
+};
+
+/// The tiles the solver can place and which tiles are allowed next to each other
+#[derive(Clone, Debug)]
+pub struct AdjacencyRules<const N: usize> {
+    tiles: Vec<Connection>,
+    lookup: HashMap<Connection, usize>,
+    allowed: Vec<[TileSet; N]>,
+    weights: Vec<f32>,
+}
+
+impl<const N: usize> AdjacencyRules<N> {
+    /// Create rules for a set of tiles, no tile is allowed next to any other until [`AdjacencyRules::allow`] is called
+    pub fn new<T: Into<Connection>>(tiles: impl IntoIterator<Item = T>) -> AdjacencyRules<N> {
+        let mut rules = AdjacencyRules {
+            tiles: Vec::new(),
+            lookup: HashMap::new(),
+            allowed: Vec::new(),
+            weights: Vec::new(),
+        };
+        for tile in tiles {
+            rules.add_tile(tile);
+        }
+        rules
+    }
+
+    /// Create rules where two tiles may be neighbours when the sockets on the sides facing each other connect,
+    /// socket `i` of a tile is the side facing direction `i`
+    pub fn from_sockets<T: Into<Connection>>(
+        tiles: impl IntoIterator<Item = (T, [Socket; N])>,
+    ) -> AdjacencyRules<N> {
+        let mut rules = AdjacencyRules::new(std::iter::empty::<Connection>());
+        let mut sockets = Vec::new();
+        for (tile, tile_sockets) in tiles {
+            let index = rules.add_tile(tile);
+            if index == sockets.len() {
+                sockets.push(tile_sockets);
+            } else {
+                sockets[index] = tile_sockets;
+            }
+        }
+        for (tile, tile_sockets) in sockets.iter().enumerate() {
+            for (direction, socket) in tile_sockets.iter().enumerate() {
+                let facing = CellGraph::<N>::opposite(direction);
+                for (neighbour, neighbour_sockets) in sockets.iter().enumerate() {
+                    if socket.connects(&neighbour_sockets[facing]) {
+                        rules.allow_index(tile, direction, neighbour);
+                    }
+                }
+            }
+        }
+        rules
+    }
+
+    /// Add a tile with a weight of 1 to the rules returning its index,
+    /// adding an existing tile returns the index it already has
+    pub fn add_tile<T: Into<Connection>>(&mut self, tile: T) -> usize {
+        let tile = tile.into();
+        if let Some(index) = self.lookup.get(&tile) {
+            return *index;
+        }
+        let index = self.tiles.len();
+        self.lookup.insert(tile.clone(), index);
+        self.tiles.push(tile);
+        let len = self.tiles.len();
+        for sides in self.allowed.iter_mut() {
+            for side in sides.iter_mut() {
+                let mut grown = TileSet::empty(len);
+                grown.union_with(side);
+                *side = grown;
+            }
+        }
+        self.allowed
+            .push(std::array::from_fn(|_| TileSet::empty(len)));
+        self.weights.push(1.);
+        index
+    }
+
+    /// Add a tile that is picked `weight` times as often as a tile with a weight of 1
+    pub fn add_weighted_tile<T: Into<Connection>>(&mut self, tile: T, weight: f32) -> usize {
+        let index = self.add_tile(tile);
+        self.weights[index] = weight.max(0.);
+        index
+    }
+
+    /// Change how often `tile` is picked relative to the other tiles, negative weights are treated as 0
+    pub fn set_weight(&mut self, tile: &Connection, weight: f32) -> Result<(), CollapseError> {
+        let index = self.index_or_err(tile)?;
+        self.weights[index] = weight.max(0.);
+        Ok(())
+    }
+
+    /// How often the tile at `index` is picked relative to the other tiles
+    pub fn weight(&self, index: usize) -> f32 {
+        self.weights[index]
+    }
+
+    /// Allow `neighbour` to sit on the `direction` side of `tile`,
+    /// this also allows `tile` on the opposite side of `neighbour`
+    pub fn allow(
+        &mut self,
+        tile: &Connection,
+        direction: usize,
+        neighbour: &Connection,
+    ) -> Result<(), CollapseError> {
+        let tile = self.index_or_err(tile)?;
+        let neighbour = self.index_or_err(neighbour)?;
+        self.allow_index(tile, direction, neighbour);
+        Ok(())
+    }
+
+    /// Allow `neighbour` to sit on any side of `tile`
+    pub fn allow_all(
+        &mut self,
+        tile: &Connection,
+        neighbour: &Connection,
+    ) -> Result<(), CollapseError> {
+        for direction in 0..N {
+            self.allow(tile, direction, neighbour)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn allow_index(&mut self, tile: usize, direction: usize, neighbour: usize) {
+        self.allowed[tile][direction].insert(neighbour);
+        self.allowed[neighbour][CellGraph::<N>::opposite(direction)].insert(tile);
+    }
+
+    /// The tiles allowed on the `direction` side of `tile`
+    pub fn allowed(&self, tile: usize, direction: usize) -> &TileSet {
+        &self.allowed[tile][direction]
+    }
+
+    /// The index the solver uses for `tile`
+    pub fn index(&self, tile: &Connection) -> Option<usize> {
+        self.lookup.get(tile).copied()
+    }
+
+    fn index_or_err(&self, tile: &Connection) -> Result<usize, CollapseError> {
+        self.index(tile)
+            .ok_or_else(|| CollapseError::UnknownTile(tile.name().to_string()))
+    }
+
+    pub fn tile(&self, index: usize) -> &Connection {
+        &self.tiles[index]
+    }
+
+    pub fn tiles(&self) -> &[Connection] {
+        &self.tiles
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
@@ -0,0 +1,112 @@
+/// A fixed size bit set of tile indices, one per candidate tile in an [`super::AdjacencyRules`]
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct TileSet {
+    bits: Vec<u64>,
+    len: usize,
+}
+
+impl TileSet {
+    /// A set that can hold `len` tiles with none of them set
+    pub fn empty(len: usize) -> TileSet {
+        TileSet {
+            bits: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    /// A set that can hold `len` tiles with all of them set
+    pub fn full(len: usize) -> TileSet {
+        let mut set = TileSet {
+            bits: vec![u64::MAX; len.div_ceil(64)],
+            len,
+        };
+        set.trim();
+        set
+    }
+
+    fn trim(&mut self) {
+        let extra = self.len % 64;
+        if extra != 0 {
+            if let Some(last) = self.bits.last_mut() {
+                *last &= (1 << extra) - 1;
+            }
+        }
+    }
+
+    /// The number of tiles this set can hold
+    pub fn capacity(&self) -> usize {
+        self.len
+    }
+
+    pub fn insert(&mut self, tile: usize) {
+        self.bits[tile / 64] |= 1 << (tile % 64);
+    }
+
+    pub fn remove(&mut self, tile: usize) {
+        self.bits[tile / 64] &= !(1 << (tile % 64));
+    }
+
+    pub fn contains(&self, tile: usize) -> bool {
+        tile < self.len && self.bits[tile / 64] & (1 << (tile % 64)) != 0
+    }
+
+    /// The number of tiles in the set
+    pub fn count(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|b| *b == 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|b| *b = 0);
+    }
+
+    /// Keep only the tiles also in `other`, returns true if anything was removed
+    pub fn intersect_with(&mut self, other: &TileSet) -> bool {
+        let mut changed = false;
+        for (bits, other) in self.bits.iter_mut().zip(other.bits.iter()) {
+            let new = *bits & *other;
+            changed |= new != *bits;
+            *bits = new;
+        }
+        changed
+    }
+
+    /// True if every tile in this set is also in `other`
+    pub fn is_subset(&self, other: &TileSet) -> bool {
+        self.bits
+            .iter()
+            .zip(other.bits.iter())
+            .all(|(bits, other)| *bits & !*other == 0)
+    }
+
+    /// Add every tile in `other` to this set
+    pub fn union_with(&mut self, other: &TileSet) {
+        for (bits, other) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *bits |= *other;
+        }
+    }
+
+    /// Iterate over the tile indices in the set in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(block, bits)| {
+            let mut bits = *bits;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let tile = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(block * 64 + tile)
+            })
+        })
+    }
+}
+
+impl std::fmt::Debug for TileSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
@@ -6,6 +6,11 @@ use std::{
 
 use crate::vertex::VertexPosition;
 
+mod quat;
+mod transform;
+pub use quat::RQuat;
+pub use transform::RTransform;
+
 pub trait VecComponent:
     Add<Output = Self>
     + Sub<Output = Self>
@@ -41,6 +46,41 @@ impl<T: VecComponent> RVec3<T> {
         self.x = x;
         self.z = z;
     }
+
+    /// Rotate about the x axis turning the same way [`RVec3::rotate_y`] does about y, so +z turns toward +y
+    pub fn rotate_x(&mut self, sin: T, cos: T) {
+        let y = self.y * cos + self.z * sin;
+        let z = self.z * cos - self.y * sin;
+        self.y = y;
+        self.z = z;
+    }
+
+    /// Rotate about the z axis turning the same way [`RVec3::rotate_y`] does about y, so +y turns toward +x
+    pub fn rotate_z(&mut self, sin: T, cos: T) {
+        let x = self.x * cos + self.y * sin;
+        let y = self.y * cos - self.x * sin;
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Multiply each axis by the matching axis of `by`
+    pub fn scale(&mut self, by: RVec3<T>) {
+        self.x *= by.x;
+        self.y *= by.y;
+        self.z *= by.z;
+    }
+
+    pub fn dot(&self, rhs: RVec3<T>) -> T {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn cross(&self, rhs: RVec3<T>) -> RVec3<T> {
+        RVec3 {
+            x: self.y * rhs.z - self.z * rhs.y,
+            y: self.z * rhs.x - self.x * rhs.z,
+            z: self.x * rhs.y - self.y * rhs.x,
+        }
+    }
 }
 
 impl<T: VecComponent + Debug> Debug for RVec3<T> {
@@ -0,0 +1,105 @@
+use super::{RTransform, RVec3, VecComponent};
+use crate::vertex::VertexPosition;
+
+/// A rotation stored as a quaternion, turning the same way as [`RVec3::rotate_y`] about its axis.
+/// Use it to build up a rotation from several steps, then turn it into an [`RTransform`] to apply it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RQuat<T: VecComponent> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
+impl<T: VertexPosition> Default for RQuat<T> {
+    fn default() -> Self {
+        RQuat::identity()
+    }
+}
+
+impl<T: VertexPosition> RQuat<T> {
+    /// No rotation
+    pub fn identity() -> RQuat<T> {
+        RQuat {
+            x: T::default(),
+            y: T::default(),
+            z: T::default(),
+            w: T::from_f32(1.),
+        }
+    }
+
+    /// Rotate `angle` radians about `axis`, which must be unit length.
+    /// The sin and cos are worked out once in `f32`, use [`RQuat::from_axis_half_sin_cos`] to keep them exact
+    pub fn from_axis_angle(axis: [f32; 3], angle: f32) -> RQuat<T> {
+        let (sin, cos) = (angle * 0.5).sin_cos();
+        RQuat::from_axis_half_sin_cos(
+            RVec3::new(
+                T::from_f32(axis[0]),
+                T::from_f32(axis[1]),
+                T::from_f32(axis[2]),
+            ),
+            T::from_f32(sin),
+            T::from_f32(cos),
+        )
+    }
+
+    /// Rotate about `axis`, which must be unit length, `sin` and `cos` are of half the angle
+    pub fn from_axis_half_sin_cos(axis: RVec3<T>, sin: T, cos: T) -> RQuat<T> {
+        // stored as the usual right handed quaternion turning the other way
+        let zero = T::default();
+        RQuat {
+            x: zero - axis.x * sin,
+            y: zero - axis.y * sin,
+            z: zero - axis.z * sin,
+            w: cos,
+        }
+    }
+
+    /// A rotation that applies `self` and then `next`
+    pub fn then(&self, next: &RQuat<T>) -> RQuat<T> {
+        let (a, b) = (next, self);
+        RQuat {
+            x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        }
+    }
+
+    pub fn rotate(&self, point: RVec3<T>) -> RVec3<T> {
+        self.to_transform().transform_vector(point)
+    }
+
+    /// The rotation as a matrix with no translation
+    pub fn to_transform(&self) -> RTransform<T> {
+        let RQuat { x, y, z, w } = *self;
+        let one = T::from_f32(1.);
+        let two = T::from_f32(2.);
+        RTransform {
+            matrix: [
+                RVec3::new(
+                    one - two * (y * y + z * z),
+                    two * (x * y - z * w),
+                    two * (x * z + y * w),
+                ),
+                RVec3::new(
+                    two * (x * y + z * w),
+                    one - two * (x * x + z * z),
+                    two * (y * z - x * w),
+                ),
+                RVec3::new(
+                    two * (x * z - y * w),
+                    two * (y * z + x * w),
+                    one - two * (x * x + y * y),
+                ),
+            ],
+            translation: RVec3::default(),
+        }
+    }
+}
+
+impl<T: VertexPosition> From<RQuat<T>> for RTransform<T> {
+    fn from(quat: RQuat<T>) -> Self {
+        quat.to_transform()
+    }
+}
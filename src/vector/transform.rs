@@ -0,0 +1,195 @@
+use super::{RQuat, RVec3, VecComponent};
+use crate::vertex::VertexPosition;
+
+/// A 3x3 matrix followed by a translation, so a chain of rotations, scales and mirrors
+/// can be applied to a mesh in one pass with [`crate::prelude::WaveMesh::transform`].
+/// The rotations only multiply and add the sin and cos they are given so fixed point tables like `HexTrig` stay exact
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RTransform<T: VecComponent> {
+    /// The rows of the matrix
+    pub matrix: [RVec3<T>; 3],
+    pub translation: RVec3<T>,
+}
+
+impl<T: VertexPosition> Default for RTransform<T> {
+    fn default() -> Self {
+        RTransform::identity()
+    }
+}
+
+impl<T: VertexPosition> RTransform<T> {
+    /// Leaves every point where it is
+    pub fn identity() -> RTransform<T> {
+        RTransform::from_scale(RVec3::new(
+            T::from_f32(1.),
+            T::from_f32(1.),
+            T::from_f32(1.),
+        ))
+    }
+
+    pub fn from_translation(translation: RVec3<T>) -> RTransform<T> {
+        RTransform {
+            translation,
+            ..RTransform::identity()
+        }
+    }
+
+    /// Scale each axis on its own, a negative scale mirrors that axis
+    pub fn from_scale(scale: RVec3<T>) -> RTransform<T> {
+        let zero = T::default();
+        RTransform {
+            matrix: [
+                RVec3::new(scale.x, zero, zero),
+                RVec3::new(zero, scale.y, zero),
+                RVec3::new(zero, zero, scale.z),
+            ],
+            translation: RVec3::default(),
+        }
+    }
+
+    /// Flip x to -x
+    pub fn mirror_x() -> RTransform<T> {
+        RTransform::from_scale(RVec3::new(
+            T::from_f32(-1.),
+            T::from_f32(1.),
+            T::from_f32(1.),
+        ))
+    }
+
+    /// Flip y to -y
+    pub fn mirror_y() -> RTransform<T> {
+        RTransform::from_scale(RVec3::new(
+            T::from_f32(1.),
+            T::from_f32(-1.),
+            T::from_f32(1.),
+        ))
+    }
+
+    /// Flip z to -z
+    pub fn mirror_z() -> RTransform<T> {
+        RTransform::from_scale(RVec3::new(
+            T::from_f32(1.),
+            T::from_f32(1.),
+            T::from_f32(-1.),
+        ))
+    }
+
+    /// Mirror across the plane of points `p` where `normal · p == distance`, `normal` must be unit length
+    pub fn from_reflection(normal: RVec3<T>, distance: T) -> RTransform<T> {
+        let two = T::from_f32(2.);
+        let one = T::from_f32(1.);
+        let zero = T::default();
+        let RVec3 { x, y, z } = normal;
+        RTransform {
+            matrix: [
+                RVec3::new(one - two * x * x, zero - two * x * y, zero - two * x * z),
+                RVec3::new(zero - two * y * x, one - two * y * y, zero - two * y * z),
+                RVec3::new(zero - two * z * x, zero - two * z * y, one - two * z * z),
+            ],
+            translation: normal * (two * distance),
+        }
+    }
+
+    /// The same rotation as [`RVec3::rotate_x`]
+    pub fn from_rotation_x(sin: T, cos: T) -> RTransform<T> {
+        let one = T::from_f32(1.);
+        let zero = T::default();
+        RTransform {
+            matrix: [
+                RVec3::new(one, zero, zero),
+                RVec3::new(zero, cos, sin),
+                RVec3::new(zero, zero - sin, cos),
+            ],
+            translation: RVec3::default(),
+        }
+    }
+
+    /// The same rotation as [`RVec3::rotate_y`]
+    pub fn from_rotation_y(sin: T, cos: T) -> RTransform<T> {
+        let one = T::from_f32(1.);
+        let zero = T::default();
+        RTransform {
+            matrix: [
+                RVec3::new(cos, zero, zero - sin),
+                RVec3::new(zero, one, zero),
+                RVec3::new(sin, zero, cos),
+            ],
+            translation: RVec3::default(),
+        }
+    }
+
+    /// The same rotation as [`RVec3::rotate_z`]
+    pub fn from_rotation_z(sin: T, cos: T) -> RTransform<T> {
+        let one = T::from_f32(1.);
+        let zero = T::default();
+        RTransform {
+            matrix: [
+                RVec3::new(cos, sin, zero),
+                RVec3::new(zero - sin, cos, zero),
+                RVec3::new(zero, zero, one),
+            ],
+            translation: RVec3::default(),
+        }
+    }
+
+    /// Rotate about any `axis` through the origin, turning the same way as [`RVec3::rotate_y`] does about y.
+    /// `axis` must be unit length, `sin` and `cos` are of the whole angle
+    pub fn from_axis_sin_cos(axis: RVec3<T>, sin: T, cos: T) -> RTransform<T> {
+        let RVec3 { x, y, z } = axis;
+        let turn = T::from_f32(1.) - cos;
+        RTransform {
+            matrix: [
+                RVec3::new(
+                    cos + turn * x * x,
+                    sin * z + turn * x * y,
+                    turn * x * z - sin * y,
+                ),
+                RVec3::new(
+                    turn * y * x - sin * z,
+                    cos + turn * y * y,
+                    sin * x + turn * y * z,
+                ),
+                RVec3::new(
+                    sin * y + turn * z * x,
+                    turn * z * y - sin * x,
+                    cos + turn * z * z,
+                ),
+            ],
+            translation: RVec3::default(),
+        }
+    }
+
+    pub fn from_quat(quat: RQuat<T>) -> RTransform<T> {
+        quat.to_transform()
+    }
+
+    /// A transform that applies `self` and then `next`
+    pub fn then(&self, next: &RTransform<T>) -> RTransform<T> {
+        let [a, b, c] = self.matrix;
+        RTransform {
+            matrix: next.matrix.map(|row| a * row.x + b * row.y + c * row.z),
+            translation: next.transform_vector(self.translation) + next.translation,
+        }
+    }
+
+    /// Move `point` by the whole transform
+    pub fn transform_point(&self, point: RVec3<T>) -> RVec3<T> {
+        self.transform_vector(point) + self.translation
+    }
+
+    /// Move `vector` by the matrix only, ignoring the translation
+    pub fn transform_vector(&self, vector: RVec3<T>) -> RVec3<T> {
+        let [a, b, c] = self.matrix;
+        RVec3::new(a.dot(vector), b.dot(vector), c.dot(vector))
+    }
+
+    pub fn determinant(&self) -> T {
+        let [a, b, c] = self.matrix;
+        a.dot(b.cross(c))
+    }
+
+    /// True when the transform mirrors space, so triangles have to be wound the other way to keep facing out
+    pub fn flips_winding(&self) -> bool {
+        self.determinant().to_f32() < 0.
+    }
+}
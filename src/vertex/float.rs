@@ -0,0 +1,164 @@
+use std::{
+    fmt::Display,
+    hash::Hash,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use super::{RawBits, VertexPosition};
+
+/// A plain `f32` or `f64` position for tools that do not need fixed point determinism.
+///
+/// Floats can not be hashed so the value is snapped to the nearest `1 / STEPS` of a unit
+/// whenever it is compared or hashed, positions in the same step are the same vertex to [`crate::prelude::WaveBuilder::bake`].
+/// The stored value is never rounded so nothing is lost when meshes are moved and rotated.
+/// Two positions closer than `1 / STEPS` that fall either side of a step are not merged,
+/// so pick `STEPS` coarse enough that rounding error in your meshes stays well inside a step
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quantised<F, const STEPS: u32 = 1024>(pub F);
+
+/// `f32` positions merged within a thousandth of a unit
+pub type QuantisedF32 = Quantised<f32>;
+/// `f64` positions merged within a thousandth of a unit
+pub type QuantisedF64 = Quantised<f64>;
+
+macro_rules! impl_quantised {
+    ($float:ty) => {
+        impl<const STEPS: u32> Quantised<$float, STEPS> {
+            /// The distance within which two positions may be merged
+            pub const EPSILON: $float = 1. / STEPS as $float;
+
+            /// The step the value is snapped to for comparing and hashing
+            pub fn step(&self) -> i64 {
+                (self.0 * STEPS as $float).round() as i64
+            }
+        }
+
+        impl<const STEPS: u32> PartialEq for Quantised<$float, STEPS> {
+            fn eq(&self, other: &Self) -> bool {
+                self.step() == other.step()
+            }
+        }
+
+        impl<const STEPS: u32> Eq for Quantised<$float, STEPS> {}
+
+        impl<const STEPS: u32> Hash for Quantised<$float, STEPS> {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.step().hash(state);
+            }
+        }
+
+        impl<const STEPS: u32> From<$float> for Quantised<$float, STEPS> {
+            fn from(value: $float) -> Self {
+                Quantised(value)
+            }
+        }
+
+        impl<const STEPS: u32> Add for Quantised<$float, STEPS> {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self::Output {
+                Quantised(self.0 + rhs.0)
+            }
+        }
+
+        impl<const STEPS: u32> Sub for Quantised<$float, STEPS> {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self::Output {
+                Quantised(self.0 - rhs.0)
+            }
+        }
+
+        impl<const STEPS: u32> Mul for Quantised<$float, STEPS> {
+            type Output = Self;
+            fn mul(self, rhs: Self) -> Self::Output {
+                Quantised(self.0 * rhs.0)
+            }
+        }
+
+        impl<const STEPS: u32> Div for Quantised<$float, STEPS> {
+            type Output = Self;
+            fn div(self, rhs: Self) -> Self::Output {
+                Quantised(self.0 / rhs.0)
+            }
+        }
+
+        impl<const STEPS: u32> Neg for Quantised<$float, STEPS> {
+            type Output = Self;
+            fn neg(self) -> Self::Output {
+                Quantised(-self.0)
+            }
+        }
+
+        impl<const STEPS: u32> AddAssign for Quantised<$float, STEPS> {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl<const STEPS: u32> SubAssign for Quantised<$float, STEPS> {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl<const STEPS: u32> MulAssign for Quantised<$float, STEPS> {
+            fn mul_assign(&mut self, rhs: Self) {
+                self.0 *= rhs.0;
+            }
+        }
+
+        impl<const STEPS: u32> DivAssign for Quantised<$float, STEPS> {
+            fn div_assign(&mut self, rhs: Self) {
+                self.0 /= rhs.0;
+            }
+        }
+
+        impl<const STEPS: u32> FromStr for Quantised<$float, STEPS> {
+            type Err = std::num::ParseFloatError;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                s.parse().map(Quantised)
+            }
+        }
+
+        impl<const STEPS: u32> Display for Quantised<$float, STEPS> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl<const STEPS: u32> VertexPosition for Quantised<$float, STEPS> {
+            fn to_f32(&self) -> f32 {
+                self.0 as f32
+            }
+            fn from_f32(val: f32) -> Self {
+                Quantised(val as $float)
+            }
+        }
+    };
+}
+
+impl_quantised!(f32);
+impl_quantised!(f64);
+
+/// The float is saved as it is, `STEPS` only matters when comparing
+impl<const STEPS: u32> RawBits for Quantised<f32, STEPS> {
+    const FORMAT: u16 = 0x204;
+    const BYTES: usize = 4;
+    fn to_raw(&self) -> u64 {
+        self.0.to_bits() as u64
+    }
+    fn from_raw(raw: u64) -> Self {
+        Quantised(f32::from_bits(raw as u32))
+    }
+}
+
+impl<const STEPS: u32> RawBits for Quantised<f64, STEPS> {
+    const FORMAT: u16 = 0x208;
+    const BYTES: usize = 8;
+    fn to_raw(&self) -> u64 {
+        self.0.to_bits()
+    }
+    fn from_raw(raw: u64) -> Self {
+        Quantised(f64::from_bits(raw))
+    }
+}
@@ -12,6 +12,9 @@ impl VertexUV for u8 {
             U8_START + U8_OFFSET * (self / 16) as f32,
         ]
     }
+    fn palette_cells() -> Vec<Self> {
+        (0..=255).collect()
+    }
 }
 
 #[cfg(not(feature = "with_bevy"))]
@@ -44,3 +47,40 @@ impl<T: 'static + LeEqU32 + Send + Sync> VertexPosition for FixedI32<T> {
         FixedI32::from_num(val)
     }
 }
+
+/// Fixed point formats are `0x100` for 32 bit or `0x180` for 64 bit plus the number of fractional bits
+const FIXED_FORMAT: u16 = 0x100;
+const FIXED_WIDE_FORMAT: u16 = 0x180;
+
+impl<T: LeEqU64> RawBits for FixedI64<T> {
+    const FORMAT: u16 = FIXED_WIDE_FORMAT | T::U16;
+    const BYTES: usize = 8;
+    fn to_raw(&self) -> u64 {
+        self.to_bits() as u64
+    }
+    fn from_raw(raw: u64) -> Self {
+        FixedI64::from_bits(raw as i64)
+    }
+}
+
+impl<T: LeEqU32> RawBits for FixedI32<T> {
+    const FORMAT: u16 = FIXED_FORMAT | T::U16;
+    const BYTES: usize = 4;
+    fn to_raw(&self) -> u64 {
+        self.to_bits() as u32 as u64
+    }
+    fn from_raw(raw: u64) -> Self {
+        FixedI32::from_bits(raw as u32 as i32)
+    }
+}
+
+impl RawBits for u8 {
+    const FORMAT: u16 = 0x300;
+    const BYTES: usize = 1;
+    fn to_raw(&self) -> u64 {
+        *self as u64
+    }
+    fn from_raw(raw: u64) -> Self {
+        raw as u8
+    }
+}
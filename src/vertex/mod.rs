@@ -2,7 +2,9 @@ use std::hash::Hash;
 
 use crate::vector::{RVec3, VecComponent};
 
+mod float;
 mod impls;
+pub use float::{Quantised, QuantisedF32, QuantisedF64};
 
 #[derive(PartialEq)]
 pub struct Vertex<P: VertexPosition, UV: VertexUV> {
@@ -30,6 +32,27 @@ pub trait VertexPosition: 'static + VecComponent + PartialEq + Hash + Send + Syn
 
 pub trait VertexUV: 'static + Copy + PartialEq + Eq + Hash + Send + Sync {
     fn to_f32x2(&self) -> [f32; 2];
+    /// The UV of a face vertex read from an OBJ file, `material` is parsed from the last `usemtl`
+    /// and `texcoord` and `normal` are the `vt` and `vn` the vertex points at if it has them.
+    /// Palette UVs like `u8` only use the material, which is the default
+    fn from_obj(material: Self, _texcoord: Option<[f32; 2]>, _normal: Option<[f32; 3]>) -> Self {
+        material
+    }
+    /// The UVs a [`crate::wave_mesh::MaterialPalette`] hands out in order to material names it does not know, none by default
+    fn palette_cells() -> Vec<Self> {
+        Vec::new()
+    }
+}
+
+/// The exact bits of a position or UV, used to save meshes in the binary format without losing precision
+pub trait RawBits: Sized {
+    /// Tells apart every type that can be saved, including its width and things like the fractional bits of fixed point numbers,
+    /// a file is only read back as the format it was saved with
+    const FORMAT: u16;
+    /// How many bytes of [`RawBits::to_raw`] are saved
+    const BYTES: usize;
+    fn to_raw(&self) -> u64;
+    fn from_raw(raw: u64) -> Self;
 }
 
 impl<P: VertexPosition, UV: VertexUV + Hash> Hash for Vertex<P, UV> {
@@ -0,0 +1,333 @@
+use super::{HashMap, WaveMesh};
+use crate::{
+    errors::ParseBinaryError,
+    vector::RVec3,
+    vertex::{RawBits, Vertex, VertexPosition, VertexUV},
+};
+
+/// Every binary mesh file starts with these bytes
+const MAGIC: &[u8; 4] = b"WFOB";
+/// Bumped whenever the layout changes, older versions are read for as long as they can be
+const VERSION: u16 = 1;
+
+/// CRC-32 as used by zip and png
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn write_raw<T: RawBits>(data: &mut Vec<u8>, value: &T) {
+    data.extend_from_slice(&value.to_raw().to_le_bytes()[..T::BYTES]);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, what: &'static str) -> Result<&'a [u8], ParseBinaryError> {
+        if self.bytes.len() < len {
+            return Err(ParseBinaryError::UnexpectedEnd(what));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self, what: &'static str) -> Result<u16, ParseBinaryError> {
+        let bytes = self.take(2, what)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self, what: &'static str) -> Result<u32, ParseBinaryError> {
+        let bytes = self.take(4, what)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn raw<T: RawBits>(&mut self, what: &'static str) -> Result<T, ParseBinaryError> {
+        let mut raw = [0; 8];
+        raw[..T::BYTES].copy_from_slice(self.take(T::BYTES, what)?);
+        Ok(T::from_raw(u64::from_le_bytes(raw)))
+    }
+}
+
+impl<P: VertexPosition + RawBits, UV: VertexUV + RawBits> WaveMesh<P, UV> {
+    /// Save named meshes, such as the map from [`WaveMesh::from_obj_str`], in the binary format read by [`WaveMesh::from_binary`].
+    /// Positions and UVs are saved as their exact bits so nothing is lost to parsing decimals,
+    /// meshes are written sorted by name so the same meshes always give the same bytes
+    pub fn to_binary(meshes: &HashMap<String, WaveMesh<P, UV>>) -> Vec<u8> {
+        let mut names: Vec<&String> = meshes.keys().collect();
+        names.sort();
+
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        data.extend_from_slice(&P::FORMAT.to_le_bytes());
+        data.extend_from_slice(&UV::FORMAT.to_le_bytes());
+        data.extend_from_slice(&(names.len() as u32).to_le_bytes());
+        for name in names {
+            let mesh = &meshes[name];
+            data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            data.extend_from_slice(name.as_bytes());
+            data.extend_from_slice(&(mesh.vertexs.len() as u32).to_le_bytes());
+            for Vertex { position, uv } in mesh.vertexs.iter() {
+                write_raw(&mut data, &position.x);
+                write_raw(&mut data, &position.y);
+                write_raw(&mut data, &position.z);
+                write_raw(&mut data, uv);
+            }
+            data.extend_from_slice(&(mesh.indices.len() as u32).to_le_bytes());
+            for index in mesh.indices.iter() {
+                data.extend_from_slice(&index.to_le_bytes());
+            }
+        }
+        let checksum = checksum(&data);
+        data.extend_from_slice(&checksum.to_le_bytes());
+        data
+    }
+
+    /// Read meshes saved with [`WaveMesh::to_binary`], the positions and UVs must be the same types they were saved as
+    pub fn from_binary(bytes: &[u8]) -> Result<HashMap<String, WaveMesh<P, UV>>, ParseBinaryError> {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(ParseBinaryError::NotBinaryMesh);
+        }
+        let mut reader = Reader {
+            bytes: &bytes[MAGIC.len()..],
+        };
+        let version = reader.u16("version")?;
+        if version != VERSION {
+            return Err(ParseBinaryError::UnsupportedVersion(version));
+        }
+        // checked before reading any further so a damaged file is not read as garbage
+        let Some(body) = bytes
+            .len()
+            .checked_sub(4)
+            .filter(|body| *body >= MAGIC.len() + 2)
+        else {
+            return Err(ParseBinaryError::UnexpectedEnd("checksum"));
+        };
+        let expected = u32::from_le_bytes([
+            bytes[body],
+            bytes[body + 1],
+            bytes[body + 2],
+            bytes[body + 3],
+        ]);
+        let found = checksum(&bytes[..body]);
+        if expected != found {
+            return Err(ParseBinaryError::ChecksumMismatch { expected, found });
+        }
+        reader.bytes = &bytes[MAGIC.len() + 2..body];
+
+        for (what, expected) in [("positions", P::FORMAT), ("uvs", UV::FORMAT)] {
+            let found = reader.u16(what)?;
+            if found != expected {
+                return Err(ParseBinaryError::WrongFormat {
+                    what,
+                    expected,
+                    found,
+                });
+            }
+        }
+
+        let count = reader.u32("mesh count")?;
+        let mut meshes = HashMap::default();
+        for _ in 0..count {
+            let len = reader.u32("name length")? as usize;
+            let name = String::from_utf8(reader.take(len, "name")?.to_vec())?;
+            let vertex_count = reader.u32("vertex count")? as usize;
+            let mut mesh = WaveMesh::new();
+            // a count is never trusted for more than the bytes left could hold
+            mesh.vertexs.reserve(vertex_count.min(reader.bytes.len()));
+            for _ in 0..vertex_count {
+                let position = RVec3::new(
+                    reader.raw("position")?,
+                    reader.raw("position")?,
+                    reader.raw("position")?,
+                );
+                mesh.vertexs.push(Vertex::new(position, reader.raw("uv")?));
+            }
+            let index_count = reader.u32("index count")? as usize;
+            mesh.indices.reserve(index_count.min(reader.bytes.len()));
+            for _ in 0..index_count {
+                let index = reader.u32("index")?;
+                if index as usize >= vertex_count {
+                    return Err(ParseBinaryError::IndexOutOfRange { mesh: name, index });
+                }
+                mesh.indices.push(index);
+            }
+            meshes.insert(name, mesh);
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(ParseBinaryError::TrailingBytes(reader.bytes.len()));
+        }
+        Ok(meshes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixed::{
+        types::extra::{U16, U8},
+        FixedI32,
+    };
+
+    type P = FixedI32<U16>;
+    type Meshes = HashMap<String, WaveMesh<P, u8>>;
+
+    const OBJ: &str = "\
+o Quad
+v 0 0 0
+v 1 0 0
+v 1 0 1
+v 0 0 1
+usemtl 3
+f 1 2 3 4
+o Triangle
+v 0 0.5 0
+v -0.25 1 0
+v 0 1 0.125
+usemtl 7
+f 1 2 3
+";
+
+    fn meshes() -> Meshes {
+        WaveMesh::from_obj_str(OBJ).unwrap()
+    }
+
+    /// Put a new checksum on the end of a changed file, so the reader gets past it to the body
+    fn sealed(mut data: Vec<u8>) -> Vec<u8> {
+        data.truncate(data.len() - 4);
+        let checksum = checksum(&data);
+        data.extend_from_slice(&checksum.to_le_bytes());
+        data
+    }
+
+    /// Where the first mesh starts, after the magic, version, formats and mesh count
+    const FIRST_MESH: usize = 4 + 2 + 2 + 2 + 4;
+
+    #[test]
+    fn round_trips() {
+        let meshes = meshes();
+        let data = WaveMesh::to_binary(&meshes);
+        let read: Meshes = WaveMesh::from_binary(&data).unwrap();
+        assert_eq!(read.len(), meshes.len());
+        for (name, mesh) in meshes.iter() {
+            let copy = &read[name];
+            assert!(copy.vertexs == mesh.vertexs, "{name} has other vertices");
+            assert_eq!(copy.indices, mesh.indices);
+        }
+        assert_eq!(WaveMesh::to_binary(&read), data);
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut data = WaveMesh::to_binary(&meshes());
+        data[0] = b'X';
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(&data),
+            Err(ParseBinaryError::NotBinaryMesh)
+        ));
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(b"WF"),
+            Err(ParseBinaryError::NotBinaryMesh)
+        ));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut data = WaveMesh::to_binary(&meshes());
+        data[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(&data),
+            Err(ParseBinaryError::UnsupportedVersion(version)) if version == VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn rejects_damaged_bytes() {
+        let mut data = WaveMesh::to_binary(&meshes());
+        data[FIRST_MESH + 5] ^= 0x10;
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(&data),
+            Err(ParseBinaryError::ChecksumMismatch { expected, found }) if expected != found
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let data = WaveMesh::to_binary(&meshes());
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(&data[..8]),
+            Err(ParseBinaryError::UnexpectedEnd("checksum"))
+        ));
+        // cut short without a checksum left at the end
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(&data[..data.len() - 2]),
+            Err(ParseBinaryError::ChecksumMismatch { .. })
+        ));
+        // cut short but sealed again, so the body itself runs out
+        let mut short = data[..data.len() - 8].to_vec();
+        short.extend_from_slice(&[0; 4]);
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(&sealed(short)),
+            Err(ParseBinaryError::UnexpectedEnd("index"))
+        ));
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        let data = WaveMesh::to_binary(&meshes());
+        assert!(matches!(
+            WaveMesh::<FixedI32<U8>, u8>::from_binary(&data),
+            Err(ParseBinaryError::WrongFormat {
+                what: "positions",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        let mut data = WaveMesh::to_binary(&meshes());
+        // the first name is "Quad", after its length
+        data[FIRST_MESH + 4] = 0xFF;
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(&sealed(data)),
+            Err(ParseBinaryError::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_indices_out_of_range() {
+        let mut data = WaveMesh::to_binary(&meshes());
+        // the last index of the last mesh, "Triangle", sits just before the checksum
+        let last = data.len() - 8;
+        data[last..last + 4].copy_from_slice(&3u32.to_le_bytes());
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(&sealed(data)),
+            Err(ParseBinaryError::IndexOutOfRange { mesh, index: 3 }) if mesh == "Triangle"
+        ));
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        let mut data = WaveMesh::to_binary(&meshes());
+        data.splice(data.len() - 4..data.len() - 4, [1, 2, 3]);
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_binary(&sealed(data)),
+            Err(ParseBinaryError::TrailingBytes(3))
+        ));
+    }
+}
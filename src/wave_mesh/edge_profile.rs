@@ -0,0 +1,150 @@
+use std::fmt::Display;
+
+use super::WaveMesh;
+use crate::vertex::{Vertex, VertexPosition, VertexUV};
+
+/// UVs are snapped to this many steps across the texture, enough to tell palette colours apart
+const UV_STEPS: f32 = 4096.;
+
+/// One side of a grid cell in the space the tile is modeled in, with the cell center at the origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgePlane {
+    /// Points out of the cell through the side
+    pub normal: [f32; 3],
+    /// How far the side is from the center of the cell
+    pub distance: f32,
+    /// The direction along the side, the cell on the other side sees it running the opposite way
+    pub tangent: [f32; 3],
+    /// The other direction across the side, the same for the cells on both sides
+    pub up: [f32; 3],
+}
+
+impl EdgePlane {
+    /// A wall of the cell facing `x`, `z` on the ground plane, `up` is the y axis
+    pub fn side(x: f32, z: f32, distance: f32) -> EdgePlane {
+        EdgePlane {
+            normal: [x, 0., z],
+            distance,
+            tangent: [-z, 0., x],
+            up: [0., 1., 0.],
+        }
+    }
+
+    /// The ceiling of the cell, `up` is the z axis
+    pub fn top(distance: f32) -> EdgePlane {
+        EdgePlane {
+            normal: [0., 1., 0.],
+            distance,
+            tangent: [1., 0., 0.],
+            up: [0., 0., 1.],
+        }
+    }
+
+    /// The floor of the cell, the tangent is reversed from [`EdgePlane::top`] like any other pair of sides
+    pub fn bottom(distance: f32) -> EdgePlane {
+        EdgePlane {
+            normal: [0., -1., 0.],
+            distance,
+            tangent: [-1., 0., 0.],
+            up: [0., 0., 1.],
+        }
+    }
+}
+
+/// The vertices of a mesh that lie on one side of its cell, snapped to a grid so small errors do not matter.
+/// Each point is `[along, up, uv x, uv y]`, sorted so two profiles are equal when they look the same.
+///
+/// Two cells line up along a shared side when the profile of one is the [`EdgeProfile::reversed`] profile of the other
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct EdgeProfile {
+    points: Vec<[i32; 4]>,
+}
+
+impl EdgeProfile {
+    fn new(mut points: Vec<[i32; 4]>) -> EdgeProfile {
+        points.sort_unstable();
+        points.dedup();
+        EdgeProfile { points }
+    }
+
+    /// The profile as seen from the cell on the other side
+    pub fn reversed(&self) -> EdgeProfile {
+        EdgeProfile::new(
+            self.points
+                .iter()
+                .map(|[along, up, u, v]| [-along, *up, *u, *v])
+                .collect(),
+        )
+    }
+
+    /// The profile of a floor or ceiling turned a quarter turn about its normal
+    pub fn quarter_turned(&self) -> EdgeProfile {
+        EdgeProfile::new(
+            self.points
+                .iter()
+                .map(|[along, up, u, v]| [-up, *along, *u, *v])
+                .collect(),
+        )
+    }
+
+    /// True when the profile looks the same from both sides so it can sit against itself
+    pub fn is_symmetric(&self) -> bool {
+        *self == self.reversed()
+    }
+
+    pub fn points(&self) -> &[[i32; 4]] {
+        &self.points
+    }
+
+    /// True when no vertex touches the side
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+impl Display for EdgeProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, [along, up, u, v]) in self.points.iter().enumerate() {
+            if i != 0 {
+                f.write_str(";")?;
+            }
+            f.write_fmt(format_args!("{},{},{},{}", along, up, u, v))?;
+        }
+        Ok(())
+    }
+}
+
+impl<P: VertexPosition, UV: VertexUV> WaveMesh<P, UV> {
+    /// Find every vertex within `tolerance` of `plane` and record where it sits along and up the side and its UV.
+    /// Positions are snapped to multiples of `tolerance`
+    pub fn edge_profile(&self, plane: &EdgePlane, tolerance: f32) -> EdgeProfile {
+        let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+        let snap = |value: f32| (value / tolerance).round() as i32;
+        let points = self
+            .vertexs
+            .iter()
+            .filter_map(|Vertex { position, uv }| {
+                let position = position.to_f32x3();
+                if (dot(position, plane.normal) - plane.distance).abs() > tolerance {
+                    return None;
+                }
+                let [u, v] = uv.to_f32x2();
+                Some([
+                    snap(dot(position, plane.tangent)),
+                    snap(dot(position, plane.up)),
+                    (u * UV_STEPS).round() as i32,
+                    (v * UV_STEPS).round() as i32,
+                ])
+            })
+            .collect();
+        EdgeProfile::new(points)
+    }
+
+    /// The profile of every side in `planes`, in the same order
+    pub fn edge_profiles(&self, planes: &[EdgePlane], tolerance: f32) -> Vec<EdgeProfile> {
+        planes
+            .iter()
+            .map(|plane| self.edge_profile(plane, tolerance))
+            .collect()
+    }
+}
@@ -0,0 +1,332 @@
+use std::fmt::{Display, Write};
+
+use super::{
+    normals::{split_normals, NormalMode},
+    HashMap, WaveBuilder, WaveMesh,
+};
+use crate::vertex::{Vertex, VertexPosition, VertexUV};
+
+/// A mesh ready to be written out, split by normal first if normals were asked for
+struct Export<'a, P: VertexPosition, UV: VertexUV> {
+    vertexs: &'a [Vertex<P, UV>],
+    /// The vertex of the mesh each written vertex comes from
+    sources: Vec<u32>,
+    normals: Option<Vec<[f32; 3]>>,
+    indices: Vec<u32>,
+}
+
+impl<'a, P: VertexPosition, UV: VertexUV> Export<'a, P, UV> {
+    fn new(vertexs: &'a [Vertex<P, UV>], indices: &[u32], normals: Option<NormalMode>) -> Self {
+        let indices = &indices[..indices.len() - indices.len() % 3];
+        match normals {
+            Some(mode) => {
+                let positions: Vec<[f32; 3]> = vertexs
+                    .iter()
+                    .map(|vertex| vertex.position.to_f32x3())
+                    .collect();
+                let split = split_normals(&positions, indices, mode);
+                Export {
+                    vertexs,
+                    sources: split.sources,
+                    normals: Some(split.normals),
+                    indices: split.indices,
+                }
+            }
+            None => Export {
+                vertexs,
+                sources: (0..vertexs.len() as u32).collect(),
+                normals: None,
+                indices: indices.to_vec(),
+            },
+        }
+    }
+
+    fn vertex(&self, index: usize) -> &Vertex<P, UV> {
+        &self.vertexs[self.sources[index] as usize]
+    }
+
+    fn positions(&self) -> impl Iterator<Item = [f32; 3]> + '_ {
+        (0..self.sources.len()).map(|index| self.vertex(index).position.to_f32x3())
+    }
+
+    fn uvs(&self) -> impl Iterator<Item = [f32; 2]> + '_ {
+        (0..self.sources.len()).map(|index| self.vertex(index).uv.to_f32x2())
+    }
+
+    /// Write the mesh as an OBJ object, every vertex has its own `vt` and `vn` so one index is used for all three.
+    /// Faces count back from the last vertex written so objects can be joined into one file as they are
+    fn obj(&self, name: &str) -> String
+    where
+        P: Display,
+        UV: Display,
+    {
+        let mut data = String::new();
+        let len = self.sources.len() as isize;
+        // writing to a String can not fail
+        let _ = writeln!(data, "o {}", name);
+        for index in 0..self.sources.len() {
+            let Vertex { position, uv } = self.vertex(index);
+            let [u, v] = uv.to_f32x2();
+            let _ = writeln!(data, "v {} {} {}", position.x, position.y, position.z);
+            // OBJ puts v = 0 at the bottom of the texture, meshes here put it at the top
+            let _ = writeln!(data, "vt {} {}", u, 1. - v);
+        }
+        for [x, y, z] in self.normals.iter().flatten() {
+            let _ = writeln!(data, "vn {} {} {}", x, y, z);
+        }
+        let mut material = None;
+        for triangle in self.indices.chunks_exact(3) {
+            // the material of a face is the UV of its first vertex, faces mixing UVs come back with one
+            let uv = self.vertex(triangle[0] as usize).uv;
+            if material != Some(uv) {
+                let _ = writeln!(data, "usemtl {}", uv);
+                material = Some(uv);
+            }
+            data.push('f');
+            for index in triangle {
+                let index = *index as isize - len;
+                match self.normals {
+                    Some(_) => {
+                        let _ = write!(data, " {}/{}/{}", index, index, index);
+                    }
+                    None => {
+                        let _ = write!(data, " {}/{}", index, index);
+                    }
+                }
+            }
+            data.push('\n');
+        }
+        data
+    }
+
+    fn ply(&self) -> Vec<u8> {
+        let mut header = format!(
+            "ply\nformat binary_little_endian 1.0\nelement vertex {}\nproperty float x\nproperty float y\nproperty float z\n",
+            self.sources.len()
+        );
+        if self.normals.is_some() {
+            header.push_str("property float nx\nproperty float ny\nproperty float nz\n");
+        }
+        header.push_str("property float s\nproperty float t\n");
+        let _ = write!(
+            header,
+            "element face {}\nproperty list uchar uint vertex_indices\nend_header\n",
+            self.indices.len() / 3
+        );
+
+        let mut data = header.into_bytes();
+        for (index, (position, uv)) in self.positions().zip(self.uvs()).enumerate() {
+            let mut values = position.to_vec();
+            if let Some(normals) = &self.normals {
+                values.extend_from_slice(&normals[index]);
+            }
+            values.extend_from_slice(&uv);
+            for value in values {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        for triangle in self.indices.chunks_exact(3) {
+            data.push(3);
+            for index in triangle {
+                data.extend_from_slice(&index.to_le_bytes());
+            }
+        }
+        data
+    }
+
+    fn gltf(&self, bin_name: &str) -> (String, Vec<u8>) {
+        // glTF does not allow empty accessors or buffers, so a mesh without faces is a scene with an empty node
+        if self.indices.is_empty() {
+            let json = concat!(
+                r#"{"asset":{"version":"2.0","generator":"bevy_wave_collapse"},"#,
+                r#""scene":0,"scenes":[{"nodes":[0]}],"nodes":[{}]}"#
+            );
+            return (json.to_string(), Vec::new());
+        }
+        let mut bin = Vec::new();
+        let mut views = Vec::new();
+        let mut accessors = Vec::new();
+        let count = self.sources.len();
+
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for position in self.positions() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+                bin.extend_from_slice(&position[axis].to_le_bytes());
+            }
+        }
+        views.push(buffer_view(0, bin.len(), ARRAY_BUFFER));
+        accessors.push(format!(
+            r#"{{"bufferView":0,"componentType":5126,"count":{},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+            count, min[0], min[1], min[2], max[0], max[1], max[2]
+        ));
+        let mut attributes = vec![r#""POSITION":0"#.to_string()];
+
+        if let Some(normals) = &self.normals {
+            let start = bin.len();
+            for value in normals.iter().flatten() {
+                bin.extend_from_slice(&value.to_le_bytes());
+            }
+            attributes.push(format!(r#""NORMAL":{}"#, accessors.len()));
+            accessors.push(accessor(views.len(), 5126, count, "VEC3"));
+            views.push(buffer_view(start, bin.len() - start, ARRAY_BUFFER));
+        }
+
+        let start = bin.len();
+        for value in self.uvs().flatten() {
+            bin.extend_from_slice(&value.to_le_bytes());
+        }
+        attributes.push(format!(r#""TEXCOORD_0":{}"#, accessors.len()));
+        accessors.push(accessor(views.len(), 5126, count, "VEC2"));
+        views.push(buffer_view(start, bin.len() - start, ARRAY_BUFFER));
+
+        let start = bin.len();
+        for index in self.indices.iter() {
+            bin.extend_from_slice(&index.to_le_bytes());
+        }
+        let indices = accessors.len();
+        accessors.push(accessor(views.len(), 5125, self.indices.len(), "SCALAR"));
+        views.push(buffer_view(start, bin.len() - start, ELEMENT_ARRAY_BUFFER));
+
+        let json = format!(
+            concat!(
+                r#"{{"asset":{{"version":"2.0","generator":"bevy_wave_collapse"}},"#,
+                r#""scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"#,
+                r#""meshes":[{{"primitives":[{{"attributes":{{{}}},"indices":{},"mode":4}}]}}],"#,
+                r#""buffers":[{{"uri":"{}","byteLength":{}}}],"#,
+                r#""bufferViews":[{}],"accessors":[{}]}}"#
+            ),
+            attributes.join(","),
+            indices,
+            json_escape(bin_name),
+            bin.len(),
+            views.join(","),
+            accessors.join(",")
+        );
+        (json, bin)
+    }
+}
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+fn buffer_view(offset: usize, length: usize, target: u32) -> String {
+    format!(
+        r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+        offset, length, target
+    )
+}
+
+fn accessor(view: usize, component: u32, count: usize, kind: &str) -> String {
+    format!(
+        r#"{{"bufferView":{},"componentType":{},"count":{},"type":"{}"}}"#,
+        view, component, count, kind
+    )
+}
+
+fn json_escape(str: &str) -> String {
+    let mut escaped = String::with_capacity(str.len());
+    for char in str.chars() {
+        match char {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            char if char.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", char as u32);
+            }
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+impl<P: VertexPosition, UV: VertexUV> WaveMesh<P, UV> {
+    /// Write the mesh as a Wavefront OBJ object called `name` that [`WaveMesh::from_obj_str`] reads back.
+    /// Each face uses the UV of its first vertex as its `usemtl`, `vt` holds the UV for other tools.
+    /// With `normals` vertices are split on hard edges like [`WaveMesh::extract_with_normals`] and `vn` is written too.
+    /// Faces use negative indices so the objects of several meshes can be joined into one file
+    pub fn to_obj(&self, name: &str, normals: Option<NormalMode>) -> String
+    where
+        P: Display,
+        UV: Display,
+    {
+        Export::new(&self.vertexs, &self.indices, normals).obj(name)
+    }
+
+    /// Write every mesh in `meshes` as one OBJ file, the reverse of [`WaveMesh::from_obj_str`].
+    /// Meshes are written sorted by name so the same meshes always give the same file
+    pub fn to_obj_file(
+        meshes: &HashMap<String, WaveMesh<P, UV>>,
+        normals: Option<NormalMode>,
+    ) -> String
+    where
+        P: Display,
+        UV: Display,
+    {
+        let mut names: Vec<&String> = meshes.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| meshes[name].to_obj(name, normals))
+            .collect()
+    }
+
+    /// Write the mesh as a binary little endian PLY file with `s` and `t` texture coordinates
+    pub fn to_ply(&self, normals: Option<NormalMode>) -> Vec<u8> {
+        Export::new(&self.vertexs, &self.indices, normals).ply()
+    }
+
+    /// Write the mesh as glTF 2.0, the JSON and the binary buffer it points at as `bin_name`,
+    /// save the buffer next to the `.gltf` file with that name.
+    /// A mesh without faces gives a scene with one empty node and no buffer
+    pub fn to_gltf(&self, bin_name: &str, normals: Option<NormalMode>) -> (String, Vec<u8>) {
+        Export::new(&self.vertexs, &self.indices, normals).gltf(bin_name)
+    }
+}
+
+impl<P: VertexPosition, UV: VertexUV> WaveBuilder<P, UV> {
+    /// Everything built so far as an OBJ object, see [`WaveMesh::to_obj`]
+    pub fn to_obj(&self, name: &str, normals: Option<NormalMode>) -> String
+    where
+        P: Display,
+        UV: Display,
+    {
+        Export::new(&self.vertexs, &self.indices, normals).obj(name)
+    }
+
+    /// Everything built so far as a binary PLY file, see [`WaveMesh::to_ply`]
+    pub fn to_ply(&self, normals: Option<NormalMode>) -> Vec<u8> {
+        Export::new(&self.vertexs, &self.indices, normals).ply()
+    }
+
+    /// Everything built so far as glTF 2.0, see [`WaveMesh::to_gltf`]
+    pub fn to_gltf(&self, bin_name: &str, normals: Option<NormalMode>) -> (String, Vec<u8>) {
+        Export::new(&self.vertexs, &self.indices, normals).gltf(bin_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixed::{types::extra::U16, FixedI32};
+
+    type P = FixedI32<U16>;
+
+    #[test]
+    fn empty_gltf_has_no_accessors() {
+        let (json, bin) = WaveMesh::<P, u8>::new().to_gltf("empty.bin", None);
+        assert!(bin.is_empty());
+        assert!(!json.contains("accessors"));
+        assert!(!json.contains("buffers"));
+    }
+
+    #[test]
+    fn gltf_counts_vertices() {
+        let meshes =
+            WaveMesh::<P, u8>::from_obj_str("o Tri\nv 0 0 0\nv 1 0 0\nv 0 0 1\nf 1 2 3\n").unwrap();
+        let (json, bin) = meshes["Tri"].to_gltf("tri.bin", None);
+        assert!(json.contains(r#""count":3,"type":"VEC3""#));
+        assert_eq!(bin.len(), 3 * 12 + 3 * 8 + 3 * 4);
+    }
+}
@@ -1,12 +1,31 @@
-use std::{marker::PhantomData, str::FromStr};
+use std::{marker::PhantomData, path::Path, str::FromStr};
 
-use crate::{vertex::VertexPosition, prelude::VertexUV};
+use crate::{vertex::{RawBits, VertexPosition}, prelude::VertexUV};
 use bevy::asset::{AssetLoader, LoadedAsset};
 
-use super::WaveMesh;
+use super::{mtl_libraries, MaterialPalette, WaveMesh};
 
-#[derive(Default)]
-pub struct WaveMeshObjLoader<P: VertexPosition, UV: VertexUV>(PhantomData<P>, PhantomData<UV>);
+/// Loads `.wfo` files, the `.mtl` files they use are read to turn material names into UVs with the palette.
+/// A material name the palette does not know gets the same free cell in every file, whatever order they load in
+pub struct WaveMeshObjLoader<P: VertexPosition, UV: VertexUV> {
+    palette: MaterialPalette<UV>,
+    _position: PhantomData<P>,
+}
+
+impl<P: VertexPosition, UV: VertexUV> Default for WaveMeshObjLoader<P, UV> {
+    fn default() -> Self {
+        WaveMeshObjLoader::new(MaterialPalette::default())
+    }
+}
+
+impl<P: VertexPosition, UV: VertexUV> WaveMeshObjLoader<P, UV> {
+    pub fn new(palette: MaterialPalette<UV>) -> Self {
+        WaveMeshObjLoader {
+            palette,
+            _position: PhantomData,
+        }
+    }
+}
 
 impl<T: 'static + VertexPosition + Send + Sync + FromStr, UV: 'static + VertexUV + Send + Sync + FromStr + Default> AssetLoader for WaveMeshObjLoader<T, UV> {
     fn extensions(&self) -> &[&str] {
@@ -19,10 +38,54 @@ impl<T: 'static + VertexPosition + Send + Sync + FromStr, UV: 'static + VertexUV
     ) -> bevy::utils::BoxedFuture<'a, Result<(), bevy::asset::Error>> {
         Box::pin(async move {
             let str = String::from_utf8_lossy(bytes);
-            for (name, mesh) in WaveMesh::<T, UV>::from_obj_str(&str)? {
+            let folder = load_context.path().parent().unwrap_or(Path::new(""));
+            let mut mtls = Vec::new();
+            for library in mtl_libraries(&str) {
+                // a missing library only matters if a material needs it, which fails with UnknownMaterial
+                if let Ok(mtl) = load_context.read_asset_bytes(folder.join(library)).await {
+                    mtls.push(mtl);
+                }
+            }
+            let mut palette = self.palette.clone();
+            for mtl in mtls.iter() {
+                palette.load_mtl(&String::from_utf8_lossy(mtl))?;
+            }
+            for (name, mesh) in WaveMesh::<T, UV>::from_obj_str_with_palette(&str, &palette)? {
+                load_context.set_labeled_asset(&name, LoadedAsset::new(mesh));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Loads `.wfb` files written by [`WaveMesh::to_binary`], much faster than parsing a `.wfo` file
+/// and the positions come back with exactly the bits they were saved with
+pub struct WaveMeshBinaryLoader<P: VertexPosition, UV: VertexUV> {
+    _marker: PhantomData<(P, UV)>,
+}
+
+impl<P: VertexPosition, UV: VertexUV> Default for WaveMeshBinaryLoader<P, UV> {
+    fn default() -> Self {
+        WaveMeshBinaryLoader {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P: VertexPosition + RawBits, UV: VertexUV + RawBits> AssetLoader for WaveMeshBinaryLoader<P, UV> {
+    fn extensions(&self) -> &[&str] {
+        &["wfb"]
+    }
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            for (name, mesh) in WaveMesh::<P, UV>::from_binary(bytes)? {
                 load_context.set_labeled_asset(&name, LoadedAsset::new(mesh));
             }
             Ok(())
         })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,237 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{errors::ParseObjError, vertex::VertexUV};
+
+/// Turns the material names used by `usemtl` into UVs.
+///
+/// A name is looked up in the names set with [`MaterialPalette::with_name`] first,
+/// then the `Kd` colour read from a `.mtl` file is matched to the closest colour set with [`MaterialPalette::with_colour`].
+/// If neither finds a UV the name is parsed as the UV itself like `usemtl 17`,
+/// any other name is given one of the [`VertexUV::palette_cells`] by [`MaterialPalette::free_cell`]
+#[derive(Debug, Clone)]
+pub struct MaterialPalette<UV: VertexUV> {
+    names: HashMap<String, UV>,
+    colours: Vec<([f32; 3], UV)>,
+    diffuse: HashMap<String, [f32; 3]>,
+    free: Vec<UV>,
+}
+
+impl<UV: VertexUV> Default for MaterialPalette<UV> {
+    fn default() -> Self {
+        MaterialPalette {
+            names: HashMap::new(),
+            colours: Vec::new(),
+            diffuse: HashMap::new(),
+            free: UV::palette_cells(),
+        }
+    }
+}
+
+impl<UV: VertexUV> MaterialPalette<UV> {
+    pub fn new() -> MaterialPalette<UV> {
+        MaterialPalette::default()
+    }
+
+    /// Always use `uv` for the material called `name`
+    pub fn with_name(mut self, name: impl Into<String>, uv: UV) -> Self {
+        self.names.insert(name.into(), uv);
+        self
+    }
+
+    /// Use `uv` for materials whose diffuse colour is closer to `colour` than any other palette colour
+    pub fn with_colour(mut self, colour: [f32; 3], uv: UV) -> Self {
+        self.colours.push((colour, uv));
+        self
+    }
+
+    /// Add several palette colours at once, such as one for every cell of a palette texture
+    pub fn with_colours(mut self, colours: impl IntoIterator<Item = ([f32; 3], UV)>) -> Self {
+        self.colours.extend(colours);
+        self
+    }
+
+    /// The UVs handed out to unknown names, an empty list turns handing them out off
+    pub fn with_free_cells(mut self, cells: impl IntoIterator<Item = UV>) -> Self {
+        self.free = cells.into_iter().collect();
+        self
+    }
+
+    /// The free cell for a material called `name` the palette does not know.
+    /// The cell is picked by a hash of the name and cells used by [`MaterialPalette::with_name`] are skipped,
+    /// so a name gets the same cell in every file whatever other names were read first.
+    /// Two unknown names can end up sharing a cell, give them a name with [`MaterialPalette::with_name`] to tell them apart
+    pub fn free_cell(&self, name: &str) -> Option<UV> {
+        if self.free.is_empty() {
+            return None;
+        }
+        let used: HashSet<UV> = self.names.values().copied().collect();
+        let start = (name_hash(name) % self.free.len() as u64) as usize;
+        self.free
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(self.free.len())
+            .find(|uv| !used.contains(uv))
+            .copied()
+    }
+
+    /// Read the `newmtl` names and `Kd` colours from a `.mtl` file
+    pub fn load_mtl(&mut self, str: &str) -> Result<(), ParseObjError> {
+        let mut current = None;
+        for (num, line) in str.lines().enumerate() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("newmtl") => {
+                    current = Some(
+                        line_name(line)
+                            .ok_or(ParseObjError::NoName(num))?
+                            .to_string(),
+                    );
+                }
+                Some("Kd") => {
+                    let Some(name) = current.clone() else {
+                        return Err(ParseObjError::ExpectedSymbol {
+                            expect: "newmtl",
+                            line: num,
+                        });
+                    };
+                    let mut colour = [0.; 3];
+                    for channel in colour.iter_mut() {
+                        *channel = words
+                            .next()
+                            .ok_or(ParseObjError::ExpectedSymbol {
+                                expect: "Kd colour",
+                                line: num,
+                            })?
+                            .parse()
+                            .map_err(|e| ParseObjError::FailedToParseFloat(e, num))?;
+                    }
+                    self.diffuse.insert(name, colour);
+                }
+                // every other material setting has no place in a palette
+                _ => continue,
+            }
+        }
+        Ok(())
+    }
+
+    /// The diffuse colour read for `name` from a `.mtl` file
+    pub fn diffuse(&self, name: &str) -> Option<[f32; 3]> {
+        self.diffuse.get(name).copied()
+    }
+
+    /// The UV for the material called `name` if the palette knows it
+    pub fn resolve(&self, name: &str) -> Option<UV> {
+        if let Some(uv) = self.names.get(name) {
+            return Some(*uv);
+        }
+        let diffuse = self.diffuse(name)?;
+        let distance = |colour: &[f32; 3]| -> f32 {
+            colour
+                .iter()
+                .zip(diffuse.iter())
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum()
+        };
+        self.colours
+            .iter()
+            .min_by(|(a, _), (b, _)| distance(a).total_cmp(&distance(b)))
+            .map(|(_, uv)| *uv)
+    }
+}
+
+impl MaterialPalette<u8> {
+    /// A palette for the 16 by 16 palette texture `u8` UVs point into, `colours[i]` is the colour of cell `i`
+    pub fn from_cells(colours: &[[f32; 3]]) -> MaterialPalette<u8> {
+        MaterialPalette::new().with_colours(
+            colours
+                .iter()
+                .take(256)
+                .enumerate()
+                .map(|(cell, colour)| (*colour, cell as u8)),
+        )
+    }
+}
+
+/// FNV-1a, written out so a name hashes the same with every version of Rust
+fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// The `.mtl` files an OBJ file refers to with `mtllib`, relative to the OBJ file
+pub fn mtl_libraries(str: &str) -> Vec<&str> {
+    names_after(str, "mtllib")
+}
+
+/// Every material an OBJ file uses with `usemtl` in the order they first appear
+pub fn material_names(str: &str) -> Vec<&str> {
+    let mut names = names_after(str, "usemtl");
+    let mut seen = HashSet::new();
+    names.retain(|name| seen.insert(*name));
+    names
+}
+
+fn names_after<'a>(str: &'a str, keyword: &str) -> Vec<&'a str> {
+    str.lines()
+        .filter(|line| line.split_whitespace().next() == Some(keyword))
+        .filter_map(line_name)
+        .collect()
+}
+
+/// The rest of a line after its first word, names are read whole since Blender writes names with spaces in them as is
+pub(crate) fn line_name(line: &str) -> Option<&str> {
+    let (_, rest) = line.trim().split_once(char::is_whitespace)?;
+    Some(rest.trim()).filter(|rest| !rest.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wave_mesh::WaveMesh;
+    use fixed::{types::extra::U16, FixedI32};
+
+    /// The UV of the first vertex of each mesh in an OBJ file that has one triangle per object
+    fn uvs(obj: &str, palette: &MaterialPalette<u8>) -> HashMap<String, u8> {
+        WaveMesh::<FixedI32<U16>, u8>::from_obj_str_with_palette(obj, palette)
+            .unwrap()
+            .into_iter()
+            .map(|(name, mesh)| (name, mesh.vertexs[0].uv))
+            .collect()
+    }
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 0 1\n";
+
+    #[test]
+    fn free_cells_do_not_depend_on_load_order() {
+        let palette = MaterialPalette::new().with_name("Stone", 3);
+        let first = format!(
+            "{}o Moss\nusemtl Moss\nf 1 2 3\no Rust\nusemtl Old Rust\nf 1 2 3\n",
+            TRIANGLE
+        );
+        let second = format!("{}o Rust\nusemtl Old Rust\nf 1 2 3\n", TRIANGLE);
+
+        let rust = palette.free_cell("Old Rust").unwrap();
+        let moss = palette.free_cell("Moss").unwrap();
+        for order in [[&first, &second], [&second, &first]] {
+            for obj in order {
+                let uvs = uvs(obj, &palette);
+                assert_eq!(uvs["Rust"], rust);
+                if let Some(uv) = uvs.get("Moss") {
+                    assert_eq!(*uv, moss);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn free_cells_skip_named_cells() {
+        let cell = MaterialPalette::<u8>::new().free_cell("Moss").unwrap();
+        let palette = MaterialPalette::new().with_name("Stone", cell);
+        assert_ne!(palette.free_cell("Moss"), Some(cell));
+        assert_eq!(palette.free_cell("Moss"), Some(cell.wrapping_add(1)));
+        let none = MaterialPalette::<u8>::new().with_free_cells([]);
+        assert_eq!(none.free_cell("Moss"), None);
+    }
+}
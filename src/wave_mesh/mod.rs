@@ -2,17 +2,31 @@ use std::hash::Hash;
 
 use crate::{
     errors::{BakeError, ParseObjError},
-    vector::RVec3,
+    vector::{RQuat, RTransform, RVec3},
     vertex::{Vertex, VertexPosition, VertexUV},
 };
 
+mod binary;
+mod edge_profile;
+mod export;
+mod material;
+mod normals;
+#[cfg(feature = "with_bevy")]
+mod tangents;
 mod wave_palate;
 
+pub use edge_profile::{EdgePlane, EdgeProfile};
+use material::line_name;
+pub use material::{material_names, mtl_libraries, MaterialPalette};
+pub use normals::{MeshWithNormals, NormalMode};
+
 #[cfg(feature = "bevy")]
 pub(crate) mod loader;
 
 #[cfg(feature = "bevy")]
 use bevy::utils::HashMap; //30% faster then std
+#[cfg(not(feature = "bevy"))]
+use std::collections::HashMap;
 
 #[cfg(feature = "bevy")]
 use uuid::uuid;
@@ -48,6 +62,55 @@ impl<P: VertexPosition, UV: VertexUV> WaveMesh<P, UV> {
             vertex.position += offset;
         }
     }
+    /// Rotate about the x axis, see [`RVec3::rotate_x`]
+    pub fn rotate_x(&mut self, sin: P, cos: P) {
+        for vertex in self.vertexs.iter_mut() {
+            vertex.position.rotate_x(sin, cos);
+        }
+    }
+    /// Rotate about the z axis, see [`RVec3::rotate_z`]
+    pub fn rotate_z(&mut self, sin: P, cos: P) {
+        for vertex in self.vertexs.iter_mut() {
+            vertex.position.rotate_z(sin, cos);
+        }
+    }
+    /// Rotate about any unit length `axis` through the origin, see [`RTransform::from_axis_sin_cos`]
+    pub fn rotate_axis(&mut self, axis: RVec3<P>, sin: P, cos: P) {
+        self.transform(&RTransform::from_axis_sin_cos(axis, sin, cos));
+    }
+    pub fn rotate_quat(&mut self, quat: RQuat<P>) {
+        self.transform(&quat.to_transform());
+    }
+    /// Scale each axis on its own, a negative scale mirrors the mesh like [`WaveMesh::transform`]
+    pub fn scale(&mut self, by: RVec3<P>) {
+        self.transform(&RTransform::from_scale(by));
+    }
+    pub fn mirror_x(&mut self) {
+        self.transform(&RTransform::mirror_x());
+    }
+    pub fn mirror_y(&mut self) {
+        self.transform(&RTransform::mirror_y());
+    }
+    pub fn mirror_z(&mut self) {
+        self.transform(&RTransform::mirror_z());
+    }
+    /// Mirror across the plane of points `p` where `normal · p == distance`, `normal` must be unit length.
+    /// Triangles are wound the other way so they still face out
+    pub fn reflect(&mut self, normal: RVec3<P>, distance: P) {
+        self.transform(&RTransform::from_reflection(normal, distance));
+    }
+    /// Move every vertex by `transform` in one pass.
+    /// If the transform mirrors the mesh every triangle is wound the other way so it still faces out
+    pub fn transform(&mut self, transform: &RTransform<P>) {
+        for vertex in self.vertexs.iter_mut() {
+            vertex.position = transform.transform_point(vertex.position);
+        }
+        if transform.flips_winding() {
+            for triangle in self.indices.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+    }
     pub fn apply_palate(&mut self, palate: impl wave_palate::WavePalate<UV>) {
         for vertex in self.vertexs.iter_mut() {
             palate.apply(&mut vertex.uv);
@@ -79,117 +142,92 @@ impl<P: VertexPosition, UV: VertexUV> WaveMesh<P, UV> {
 }
 
 impl<P: VertexPosition + std::str::FromStr, UV: VertexUV + std::str::FromStr + Default> WaveMesh<P, UV> {
+    /// Parse every object in an OBJ file, `o` starts a new mesh named after it and so does `g` in files without objects.
+    /// Groups inside an object, such as the ones Blender writes per material, stay part of the object.
+    /// A name seen before carries on the mesh with that name so objects split up by the exporter come back as one.
+    /// Faces with more than 3 vertices are split into a fan of triangles,
+    /// `vt` and `vn` are passed to [`VertexUV::from_obj`] along with the colour from the last `usemtl`.
+    /// Materials with a UV for a name like `usemtl 17` are that UV, other names get a free palette cell,
+    /// use [`WaveMesh::from_obj_str_with_palette`] to choose the UV of named materials
     pub fn from_obj_str(str: &str) -> Result<HashMap<String, WaveMesh<P, UV>>, ParseObjError> {
+        WaveMesh::from_obj_str_with_palette(str, &MaterialPalette::default())
+    }
+
+    /// Parse an OBJ file turning `usemtl` names into UVs with `palette`,
+    /// names the palette does not know are parsed as the UV itself or given a [`MaterialPalette::free_cell`]
+    pub fn from_obj_str_with_palette(
+        str: &str,
+        palette: &MaterialPalette<UV>,
+    ) -> Result<HashMap<String, WaveMesh<P, UV>>, ParseObjError> {
         let mut meshs = HashMap::new();
         let mut current_mesh = WaveMesh::new();
         let mut current_color = UV::default();
         let mut current_name = String::new();
-        let mut first = true;
+        let mut named = false;
+        let mut in_object = false;
         let mut points = Vec::new();
+        let mut texcoords = Vec::new();
+        let mut normals = Vec::new();
         let mut vertex_map = HashMap::new();
+        let mut face = Vec::new();
         for (num, line) in str.lines().enumerate() {
-            let mut words = line.split(' ');
-            match {
-                let Some(w) = words.next() else {continue;};
-                w
-            } {
-                "#" | "vt" | "s" | "l" | "mtllib" | "vn" => continue,
+            let mut words = line.split_whitespace();
+            let Some(w) = words.next() else {
+                continue;
+            };
+            match w {
+                w if w.starts_with('#') => continue,
+                "s" | "l" | "mtllib" => continue,
                 "usemtl" => {
-                    current_color = words
-                        .next()
-                        .ok_or(ParseObjError::ExpectedSymbol {
-                            expect: "Color",
-                            line: num,
-                        })?
-                        .parse()
-                        .or_else(|_| Err(ParseObjError::FailedToParse("failed to parse usemtl", num)))?;
+                    let name = line_name(line).ok_or(ParseObjError::ExpectedSymbol {
+                        expect: "Color",
+                        line: num,
+                    })?;
+                    current_color = palette
+                        .resolve(name)
+                        .or_else(|| name.parse().ok())
+                        .or_else(|| palette.free_cell(name))
+                        .ok_or_else(|| ParseObjError::UnknownMaterial(name.to_string(), num))?;
                 }
                 "f" => {
-                    let mut f0 = words
-                        .next()
-                        .ok_or(ParseObjError::ExpectedSymbol {
-                            expect: "Face Index 0",
-                            line: num,
-                        })?
-                        .split('/');
-                    let f0_0 = f0
-                        .next()
-                        .ok_or(ParseObjError::ExpectedSymbol {
-                            expect: "Face Vertex 0",
-                            line: num,
-                        })?
-                        .parse::<usize>()
-                        .or_else(|e| Err(ParseObjError::FailedToParseInt(e, num)))?
-                        - 1;
-                    //let f0_1 = f0.next().ok_or(ParseObjError::NoPoint(1, num))?.parse::<usize>().or_else(|e| Err(ParseObjError::Int(e, num)))?;
-                    //let f0_2 = f0.next().ok_or(ParseObjError::NoPoint(2, num))?.parse::<usize>().or_else(|e| Err(ParseObjError::Int(e, num)))?;
-                    let mut f1 = words
-                        .next()
-                        .ok_or(ParseObjError::ExpectedSymbol {
-                            expect: "Face Index 1",
-                            line: num,
-                        })?
-                        .split('/');
-                    let f1_0 = f1
-                        .next()
-                        .ok_or(ParseObjError::ExpectedSymbol {
-                            expect: "Face Vertex 1",
-                            line: num,
-                        })?
-                        .parse::<usize>()
-                        .or_else(|e| Err(ParseObjError::FailedToParseInt(e, num)))?
-                        - 1;
-                    //let f1_1 = f1.next().ok_or(ParseObjError::NoPoint(1, num))?.parse::<usize>().or_else(|e| Err(ParseObjError::Int(e, num)))?;
-                    //let f1_2 = f1.next().ok_or(ParseObjError::NoPoint(2, num))?.parse::<usize>().or_else(|e| Err(ParseObjError::Int(e, num)))?;
-
-                    let mut f2 = words
-                        .next()
-                        .ok_or(ParseObjError::ExpectedSymbol {
+                    face.clear();
+                    for word in words {
+                        let mut parts = word.split('/');
+                        let point = obj_index(parts.next(), points.len(), num)?;
+                        let texcoord = match parts.next() {
+                            Some(part) if !part.is_empty() => {
+                                Some(texcoords[obj_index(Some(part), texcoords.len(), num)?])
+                            }
+                            _ => None,
+                        };
+                        let normal = match parts.next() {
+                            Some(part) if !part.is_empty() => {
+                                Some(normals[obj_index(Some(part), normals.len(), num)?])
+                            }
+                            _ => None,
+                        };
+                        let vertex = Vertex {
+                            position: points[point],
+                            uv: UV::from_obj(current_color, texcoord, normal),
+                        };
+                        let id = *vertex_map.entry(vertex).or_insert_with(|| {
+                            let id = current_mesh.vertexs.len() as u32;
+                            current_mesh.vertexs.push(vertex);
+                            id
+                        });
+                        face.push(id);
+                    }
+                    if face.len() < 3 {
+                        return Err(ParseObjError::ExpectedSymbol {
                             expect: "Face Index 2",
                             line: num,
-                        })?
-                        .split('/');
-                    let f2_0 = f2
-                        .next()
-                        .ok_or(ParseObjError::ExpectedSymbol {
-                            expect: "Face Vertex 2",
-                            line: num,
-                        })?
-                        .parse::<usize>()
-                        .or_else(|e| Err(ParseObjError::FailedToParseInt(e, num)))?
-                        - 1;
-                    //let f2_1 = f2.next().ok_or(ParseObjError::NoPoint(1, num))?.parse::<usize>().or_else(|e| Err(ParseObjError::Int(e, num)))?;
-                    //let f2_2 = f2.next().ok_or(ParseObjError::NoPoint(2, num))?.parse::<usize>().or_else(|e| Err(ParseObjError::Int(e, num)))?;
-                    let vertex = Vertex {
-                        position: points[f0_0],
-                        uv: current_color,
-                    };
-                    let id = *vertex_map.entry(vertex).or_insert_with(|| {
-                        let id = current_mesh.vertexs.len() as u32;
-                        current_mesh.vertexs.push(vertex);
-                        id
-                    });
-                    current_mesh.indices.push(id);
-                    let vertex = Vertex {
-                        position: points[f1_0],
-                        uv: current_color,
-                    };
-                    let id = *vertex_map.entry(vertex).or_insert_with(|| {
-                        let id = current_mesh.vertexs.len() as u32;
-                        current_mesh.vertexs.push(vertex);
-                        id
-                    });
-                    current_mesh.indices.push(id);
-                    let vertex = Vertex {
-                        position: points[f2_0],
-                        uv: current_color,
-                    };
-                    let id = *vertex_map.entry(vertex).or_insert_with(|| {
-                        let id = current_mesh.vertexs.len() as u32;
-                        current_mesh.vertexs.push(vertex);
-                        id
-                    });
-                    current_mesh.indices.push(id);
+                        });
+                    }
+                    for i in 1..face.len() - 1 {
+                        current_mesh
+                            .indices
+                            .extend([face[0], face[i], face[i + 1]]);
+                    }
                 }
                 "v" => {
                     let x = words
@@ -199,7 +237,7 @@ impl<P: VertexPosition + std::str::FromStr, UV: VertexUV + std::str::FromStr + D
                             line: num,
                         })?
                         .parse::<P>()
-                        .or_else(|_| Err(ParseObjError::FailedToParse("Vertex x", num)))?;
+                        .map_err(|_| ParseObjError::FailedToParse("Vertex x", num))?;
                     let y = words
                         .next()
                         .ok_or(ParseObjError::ExpectedSymbol {
@@ -207,7 +245,7 @@ impl<P: VertexPosition + std::str::FromStr, UV: VertexUV + std::str::FromStr + D
                             line: num,
                         })?
                         .parse::<P>()
-                        .or_else(|_| Err(ParseObjError::FailedToParse("Vertex y", num)))?;
+                        .map_err(|_| ParseObjError::FailedToParse("Vertex y", num))?;
                     let z = words
                         .next()
                         .ok_or(ParseObjError::ExpectedSymbol {
@@ -215,28 +253,55 @@ impl<P: VertexPosition + std::str::FromStr, UV: VertexUV + std::str::FromStr + D
                             line: num,
                         })?
                         .parse::<P>()
-                        .or_else(|_| Err(ParseObjError::FailedToParse("Vertex z", num)))?;
+                        .map_err(|_| ParseObjError::FailedToParse("Vertex z", num))?;
                     points.push(RVec3::new(x, y, z));
                 }
-                "o" => {
-                    if !first {
+                "vt" => {
+                    let u = obj_float(words.next(), "Texture u", num)?;
+                    // the v coordinate is optional in the spec and defaults to 0
+                    let v = match words.next() {
+                        Some(word) => obj_float(Some(word), "Texture v", num)?,
+                        None => 0.,
+                    };
+                    texcoords.push([u, v]);
+                }
+                "vn" => {
+                    let x = obj_float(words.next(), "Normal x", num)?;
+                    let y = obj_float(words.next(), "Normal y", num)?;
+                    let z = obj_float(words.next(), "Normal z", num)?;
+                    normals.push([x, y, z]);
+                }
+                "g" if in_object => continue,
+                "o" | "g" => {
+                    in_object |= w == "o";
+                    if !current_mesh.vertexs.is_empty() {
                         meshs.insert(current_name, current_mesh);
-                    } else {
-                        first = false;
                     }
-                    current_mesh = WaveMesh::new();
-                    current_name = words.next().ok_or(ParseObjError::NoName(num))?.split(".").next().expect("Split always yelds at least once").to_string();
-                    vertex_map.clear();
+                    named = true;
+                    current_name = words
+                        .next()
+                        .ok_or(ParseObjError::NoName(num))?
+                        .split('.')
+                        .next()
+                        .expect("Split always yelds at least once")
+                        .to_string();
+                    current_mesh = meshs.remove(&current_name).unwrap_or_else(WaveMesh::new);
+                    vertex_map = current_mesh
+                        .vertexs
+                        .iter()
+                        .enumerate()
+                        .map(|(id, vertex)| (*vertex, id as u32))
+                        .collect();
                 }
                 w => {
                     return Err(ParseObjError::UnknownSymbol(w.to_string(), num));
                 }
             }
         }
-        if !first {
+        if named || !current_mesh.vertexs.is_empty() {
             meshs.insert(current_name, current_mesh);
         }
-        if meshs.len() == 0 {
+        if meshs.is_empty() {
             Err(ParseObjError::NoMeshs)
         } else {
             Ok(meshs)
@@ -244,6 +309,32 @@ impl<P: VertexPosition + std::str::FromStr, UV: VertexUV + std::str::FromStr + D
     }
 }
 
+/// Turn a 1 based OBJ index into a 0 based one, negative indices count back from the last one read
+fn obj_index(word: Option<&str>, len: usize, line: usize) -> Result<usize, ParseObjError> {
+    let index = word
+        .ok_or(ParseObjError::ExpectedSymbol {
+            expect: "Face Vertex",
+            line,
+        })?
+        .parse::<isize>()
+        .map_err(|e| ParseObjError::FailedToParseInt(e, line))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        len as isize + index
+    };
+    if index == 0 || resolved < 0 || resolved as usize >= len {
+        return Err(ParseObjError::IndexOutOfRange { index, line });
+    }
+    Ok(resolved as usize)
+}
+
+fn obj_float(word: Option<&str>, expect: &'static str, line: usize) -> Result<f32, ParseObjError> {
+    word.ok_or(ParseObjError::ExpectedSymbol { expect, line })?
+        .parse()
+        .map_err(|e| ParseObjError::FailedToParseFloat(e, line))
+}
+
 #[cfg(feature = "bevy")]
 impl<T: VertexPosition, UV: VertexUV> bevy::reflect::TypeUuid for WaveMesh<T, UV> {
     const TYPE_UUID: bevy::utils::Uuid = uuid!("c222c5a0-c488-4642-923d-d9b6eda4b7d3");
@@ -289,6 +380,74 @@ impl<P: VertexPosition, UV: VertexUV + Hash> WaveBuilder<P, UV> {
         Ok(())
     }
 
+    /// Move everything in `other` into this builder as if it had been baked here after what is already in it.
+    /// Vertexes `other` baked are combined with the ones baked here and its indices remapped to match,
+    /// vertexes it added with [`WaveBuilder::add`] are kept apart
+    pub fn merge(&mut self, other: WaveBuilder<P, UV>) {
+        if self.vertexs.is_empty() && self.indices.is_empty() {
+            *self = other;
+            return;
+        }
+        let remap: Vec<u32> = other
+            .vertexs
+            .iter()
+            .enumerate()
+            .map(|(index, vertex)| {
+                let id = self.vertexs.len() as u32;
+                if other.map.get(vertex) != Some(&(index as u32)) {
+                    self.vertexs.push(*vertex);
+                    return id;
+                }
+                *self.map.entry(*vertex).or_insert_with(|| {
+                    self.vertexs.push(*vertex);
+                    id
+                })
+            })
+            .collect();
+        self.indices
+            .extend(other.indices.iter().map(|i| remap[*i as usize]));
+    }
+
+    /// Split `items` across up to `threads` threads that each bake into their own builder with `bake`,
+    /// then merge the builders in order so the result is the same as baking every item into one builder.
+    /// The first error in the order of `items` is returned
+    pub fn bake_parallel<T: Sync>(
+        items: &[T],
+        threads: usize,
+        bake: impl Fn(&mut WaveBuilder<P, UV>, &T) -> Result<(), BakeError> + Sync,
+    ) -> Result<WaveBuilder<P, UV>, BakeError> {
+        let bake = &bake;
+        let per_thread = items.len().div_ceil(threads.max(1)).max(1);
+        let builders = std::thread::scope(|scope| {
+            let handles: Vec<_> = items
+                .chunks(per_thread)
+                .map(|items| {
+                    scope.spawn(move || {
+                        let mut builder = WaveBuilder::new();
+                        for item in items {
+                            bake(&mut builder, item)?;
+                        }
+                        Ok(builder)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect::<Result<Vec<_>, BakeError>>()
+        })?;
+
+        let mut main = WaveBuilder::new();
+        for builder in builders {
+            main.merge(builder);
+        }
+        Ok(main)
+    }
+
     pub fn new() -> WaveBuilder<P, UV> {
         WaveBuilder {
             vertexs: Vec::new(),
@@ -327,6 +486,14 @@ impl<P: VertexPosition, UV: VertexUV + Hash> WaveBuilder<P, UV> {
         mesh
     }
 
+    /// Turn everything built so far into a single mesh
+    pub fn into_mesh(self) -> WaveMesh<P, UV> {
+        WaveMesh {
+            vertexs: self.vertexs,
+            indices: self.indices,
+        }
+    }
+
     pub fn vertex_len(&self) -> usize {
         self.vertexs.len()
     }
@@ -334,4 +501,250 @@ impl<P: VertexPosition, UV: VertexUV + Hash> WaveBuilder<P, UV> {
     pub fn indices_len(&self) -> usize {
         self.indices.len()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixed::{types::extra::U16, FixedI32};
+
+    type P = FixedI32<U16>;
+
+    /// Keeps the `vt` and `vn` of each vertex, in hundredths, so the tests can see them
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+    struct ObjUV {
+        material: u8,
+        texcoord: Option<[i32; 2]>,
+        normal: Option<[i32; 3]>,
+    }
+
+    impl VertexUV for ObjUV {
+        fn to_f32x2(&self) -> [f32; 2] {
+            self.material.to_f32x2()
+        }
+        fn from_obj(material: Self, texcoord: Option<[f32; 2]>, normal: Option<[f32; 3]>) -> Self {
+            let hundredths = |value: f32| (value * 100.).round() as i32;
+            ObjUV {
+                material: material.material,
+                texcoord: texcoord.map(|texcoord| texcoord.map(hundredths)),
+                normal: normal.map(|normal| normal.map(hundredths)),
+            }
+        }
+    }
+
+    impl std::str::FromStr for ObjUV {
+        type Err = std::num::ParseIntError;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(ObjUV {
+                material: s.parse()?,
+                ..ObjUV::default()
+            })
+        }
+    }
+
+    fn parse(obj: &str) -> HashMap<String, WaveMesh<P, u8>> {
+        WaveMesh::from_obj_str(obj).unwrap()
+    }
+
+    fn positions<UV: VertexUV>(mesh: &WaveMesh<P, UV>) -> Vec<[f32; 3]> {
+        mesh.vertexs
+            .iter()
+            .map(|vertex| vertex.position.to_f32x3())
+            .collect()
+    }
+
+    const SQUARE: &str = "v 0 0 0
+v 1 0 0
+v 1 0 1
+v 0 0 1
+";
+
+    #[test]
+    fn fans_out_polygons() {
+        let meshes = parse(&format!("o Pentagon\n{}v 0.5 0 1.5\nf 1 2 3 5 4\n", SQUARE));
+        let mesh = &meshes["Pentagon"];
+        assert_eq!(
+            positions(mesh),
+            [
+                [0., 0., 0.],
+                [1., 0., 0.],
+                [1., 0., 1.],
+                [0.5, 0., 1.5],
+                [0., 0., 1.]
+            ]
+        );
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3, 0, 3, 4]);
+    }
+
+    #[test]
+    fn negative_indices_count_back() {
+        let meshes = parse(&format!(
+            "o Square\n{}f -4 -3 -2\nv 2 0 0\nf 1 -1 -2\n",
+            SQUARE
+        ));
+        let mesh = &meshes["Square"];
+        assert_eq!(
+            positions(mesh),
+            [
+                [0., 0., 0.],
+                [1., 0., 0.],
+                [1., 0., 1.],
+                [2., 0., 0.],
+                [0., 0., 1.]
+            ]
+        );
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 3, 4]);
+        assert!(matches!(
+            WaveMesh::<P, u8>::from_obj_str("v 0 0 0\nf -1 -2 -1\n"),
+            Err(ParseObjError::IndexOutOfRange { index: -2, line: 1 })
+        ));
+    }
+
+    #[test]
+    fn reads_texcoords_and_normals() {
+        let obj = format!(
+            "o Quad\n{}vt 0.25 0.5\nvt 0.75\nvn 0 1 0\nusemtl 7\nf 1/1/1 2/2/1 3//1\nf 1/2 3 4\n",
+            SQUARE
+        );
+        let meshes = WaveMesh::<P, ObjUV>::from_obj_str(&obj).unwrap();
+        let mesh = &meshes["Quad"];
+        let uvs: Vec<_> = mesh.vertexs.iter().map(|vertex| vertex.uv).collect();
+        let uv = |texcoord, normal| ObjUV {
+            material: 7,
+            texcoord,
+            normal,
+        };
+        let up = Some([0, 100, 0]);
+        assert_eq!(
+            uvs,
+            [
+                uv(Some([25, 50]), up),
+                uv(Some([75, 0]), up),
+                uv(None, up),
+                // the same point with another texcoord is another vertex
+                uv(Some([75, 0]), None),
+                uv(None, None),
+                uv(None, None),
+            ]
+        );
+        assert_eq!(
+            positions(mesh),
+            [
+                [0., 0., 0.],
+                [1., 0., 0.],
+                [1., 0., 1.],
+                [0., 0., 0.],
+                [1., 0., 1.],
+                [0., 0., 1.]
+            ]
+        );
+        assert_eq!(mesh.indices, [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn groups_name_meshes_only_outside_objects() {
+        let groups = parse(&format!("{}g First\nf 1 2 3\ng Second\nf 1 3 4\n", SQUARE));
+        assert_eq!(groups["First"].indices, [0, 1, 2]);
+        assert_eq!(groups["Second"].indices, [0, 1, 2]);
+        assert_eq!(
+            positions(&groups["Second"]),
+            [[0., 0., 0.], [1., 0., 1.], [0., 0., 1.]]
+        );
+
+        let object = parse(&format!(
+            "o Floor\n{}g Grass\nf 1 2 3\ng Dirt\nf 1 3 4\n",
+            SQUARE
+        ));
+        assert_eq!(object.len(), 1);
+        assert_eq!(object["Floor"].indices, [0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn merges_repeated_objects() {
+        let obj = format!(
+            "{}o Floor\nf 1 2 3\no Wall.001\nf 1 2 4\no Floor.001\nf 1 3 4\no Wall\nf 2 3 4\n",
+            SQUARE
+        );
+        let meshes = parse(&obj);
+        assert_eq!(meshes.len(), 2);
+        let floor = &meshes["Floor"];
+        assert_eq!(
+            positions(floor),
+            [[0., 0., 0.], [1., 0., 0.], [1., 0., 1.], [0., 0., 1.]]
+        );
+        assert_eq!(floor.indices, [0, 1, 2, 0, 2, 3]);
+        let wall = &meshes["Wall"];
+        assert_eq!(
+            positions(wall),
+            [[0., 0., 0.], [1., 0., 0.], [0., 0., 1.], [1., 0., 1.]]
+        );
+        assert_eq!(wall.indices, [0, 1, 2, 1, 3, 2]);
+    }
+
+    /// Bake the square at `item`, every fifth one is added instead so it keeps its own vertexes
+    fn bake_square(builder: &mut WaveBuilder<P, u8>, square: &WaveMesh<P, u8>, item: &(i32, i32)) {
+        let offset = RVec3::new(P::from_num(item.0), P::ZERO, P::from_num(item.1));
+        match (item.0 + item.1) % 5 {
+            0 => builder.add(offset, square),
+            _ => builder.bake(offset, square),
+        }
+        .unwrap();
+    }
+
+    /// A square with two materials and a grid of places for it that share edges
+    fn squares() -> (WaveMesh<P, u8>, Vec<(i32, i32)>) {
+        let square = parse(&format!(
+            "o Square\n{}usemtl 1\nf 1 2 3\nusemtl 2\nf 1 3 4\n",
+            SQUARE
+        ))
+        .remove("Square")
+        .unwrap();
+        let items = (0..7).flat_map(|x| (0..3).map(move |z| (x, z))).collect();
+        (square, items)
+    }
+
+    fn assert_same(builder: &WaveBuilder<P, u8>, expected: &WaveBuilder<P, u8>) {
+        assert!(builder.vertexs == expected.vertexs);
+        assert_eq!(builder.indices, expected.indices);
+    }
+
+    #[test]
+    fn bake_parallel_matches_one_builder() {
+        let (square, items) = squares();
+        let mut sequential = WaveBuilder::new();
+        for item in items.iter() {
+            bake_square(&mut sequential, &square, item);
+        }
+        for threads in [1, 2, 3, 5, 64] {
+            let parallel = WaveBuilder::bake_parallel(&items, threads, |builder, item| {
+                bake_square(builder, &square, item);
+                Ok(())
+            })
+            .unwrap();
+            assert_same(&parallel, &sequential);
+        }
+    }
+
+    #[test]
+    fn merge_matches_one_builder() {
+        let (square, items) = squares();
+        let mut sequential = WaveBuilder::new();
+        for item in items.iter() {
+            bake_square(&mut sequential, &square, item);
+        }
+        for split in [0, 1, 7, items.len()] {
+            let mut first = WaveBuilder::new();
+            let mut second = WaveBuilder::new();
+            for (index, item) in items.iter().enumerate() {
+                let builder = if index < split {
+                    &mut first
+                } else {
+                    &mut second
+                };
+                bake_square(builder, &square, item);
+            }
+            first.merge(second);
+            assert_same(&first, &sequential);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use super::{WaveBuilder, WaveMesh};
+use crate::vertex::{Vertex, VertexPosition, VertexUV};
+
+/// How normals are worked out for [`WaveMesh::extract_with_normals`] and [`WaveBuilder::extract_with_normals`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalMode {
+    /// Every face gets its own normal so each triangle is lit as a flat surface
+    Flat,
+    /// Faces meeting at a vertex share a normal weighted by the angle of each face at the vertex,
+    /// faces meeting at more than `crease_angle` radians keep a hard edge between them
+    Smooth { crease_angle: f32 },
+}
+
+impl Default for NormalMode {
+    fn default() -> Self {
+        NormalMode::Smooth {
+            crease_angle: 30f32.to_radians(),
+        }
+    }
+}
+
+/// Positions, uvs, normals and indices in the layout a renderer wants
+pub type MeshWithNormals = (Vec<[f32; 3]>, Vec<[f32; 2]>, Vec<[f32; 3]>, Vec<u32>);
+
+/// A mesh split so every vertex has one normal
+pub(crate) struct SplitNormals {
+    /// The vertex of the input each output vertex was made from
+    pub sources: Vec<u32>,
+    pub normals: Vec<[f32; 3]>,
+    pub indices: Vec<u32>,
+}
+
+/// Faces closer than this are treated as facing the same way by [`NormalMode::Flat`]
+const FLAT_COS: f32 = 0.9999;
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub(crate) fn normalize(a: [f32; 3]) -> Option<[f32; 3]> {
+    let length = dot(a, a).sqrt();
+    (length > f32::EPSILON).then(|| [a[0] / length, a[1] / length, a[2] / length])
+}
+
+/// Work out normals for a triangle list, splitting vertices that sit on a hard edge
+/// so vertices merged by [`WaveBuilder::bake`] are only shared by faces that are smooth with each other
+pub(crate) fn split_normals(
+    positions: &[[f32; 3]],
+    indices: &[u32],
+    mode: NormalMode,
+) -> SplitNormals {
+    let min_cos = match mode {
+        NormalMode::Flat => FLAT_COS,
+        NormalMode::Smooth { crease_angle } => crease_angle.cos(),
+    };
+    let indices = &indices[..indices.len() - indices.len() % 3];
+
+    let mut face_normals = Vec::with_capacity(indices.len() / 3);
+    let mut corner_weights = vec![0.; indices.len()];
+    for (face, triangle) in indices.chunks_exact(3).enumerate() {
+        let points = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+        face_normals.push(
+            normalize(cross(sub(points[1], points[0]), sub(points[2], points[0])))
+                .unwrap_or_default(),
+        );
+        for corner in 0..3 {
+            let here = points[corner];
+            let (Some(a), Some(b)) = (
+                normalize(sub(points[(corner + 1) % 3], here)),
+                normalize(sub(points[(corner + 2) % 3], here)),
+            ) else {
+                continue;
+            };
+            corner_weights[face * 3 + corner] = dot(a, b).clamp(-1., 1.).acos();
+        }
+    }
+
+    let mut corners = vec![Vec::new(); positions.len()];
+    for (corner, index) in indices.iter().enumerate() {
+        corners[*index as usize].push(corner);
+    }
+
+    let mut split = SplitNormals {
+        sources: Vec::new(),
+        normals: Vec::new(),
+        indices: vec![0; indices.len()],
+    };
+    let mut lookup = HashMap::new();
+    for (vertex, shared) in corners.iter().enumerate() {
+        for corner in shared.iter() {
+            let facing = face_normals[corner / 3];
+            let mut sum = [0.; 3];
+            // summed in the same order for every corner so corners in the same group get bit identical normals
+            for other in shared.iter() {
+                let other_facing = face_normals[other / 3];
+                if dot(facing, other_facing) >= min_cos {
+                    for axis in 0..3 {
+                        sum[axis] += other_facing[axis] * corner_weights[*other];
+                    }
+                }
+            }
+            let normal = normalize(sum)
+                .or_else(|| normalize(facing))
+                .unwrap_or([0., 1., 0.]);
+            let id = *lookup
+                .entry((vertex, normal.map(f32::to_bits)))
+                .or_insert_with(|| {
+                    split.sources.push(vertex as u32);
+                    split.normals.push(normal);
+                    split.sources.len() as u32 - 1
+                });
+            split.indices[*corner] = id;
+        }
+    }
+    split
+}
+
+/// Split `vertexs` by normal and flatten them ready to hand to a renderer
+pub(crate) fn extract_with_normals<P: VertexPosition, UV: VertexUV>(
+    vertexs: &[Vertex<P, UV>],
+    indices: &[u32],
+    mode: NormalMode,
+) -> MeshWithNormals {
+    let positions: Vec<[f32; 3]> = vertexs
+        .iter()
+        .map(|vertex| vertex.position.to_f32x3())
+        .collect();
+    let split = split_normals(&positions, indices, mode);
+    let uvs = split
+        .sources
+        .iter()
+        .map(|source| vertexs[*source as usize].uv.to_f32x2())
+        .collect();
+    let positions = split
+        .sources
+        .iter()
+        .map(|source| positions[*source as usize])
+        .collect();
+    (positions, uvs, split.normals, split.indices)
+}
+
+#[cfg(feature = "with_bevy")]
+fn mesh_with_normals(
+    topology: bevy::render::render_resource::PrimitiveTopology,
+    (positions, uvs, normals, indices): MeshWithNormals,
+) -> bevy::prelude::Mesh {
+    use bevy::prelude::Mesh;
+    let mut mesh = Mesh::new(topology);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(bevy::render::mesh::Indices::U32(indices)));
+    mesh
+}
+
+impl<P: VertexPosition, UV: VertexUV> WaveMesh<P, UV> {
+    /// Like [`WaveMesh::extract`] with a normal for every vertex,
+    /// vertices on a hard edge are split so each side keeps its own normal
+    pub fn extract_with_normals(&self, mode: NormalMode) -> MeshWithNormals {
+        extract_with_normals(&self.vertexs, &self.indices, mode)
+    }
+
+    /// Like [`WaveMesh::extract_mesh`] with `Mesh::ATTRIBUTE_NORMAL` filled in
+    #[cfg(feature = "with_bevy")]
+    pub fn extract_mesh_with_normals(
+        &self,
+        topology: bevy::render::render_resource::PrimitiveTopology,
+        mode: NormalMode,
+    ) -> bevy::prelude::Mesh {
+        mesh_with_normals(topology, self.extract_with_normals(mode))
+    }
+}
+
+impl<P: VertexPosition, UV: VertexUV> WaveBuilder<P, UV> {
+    /// Like [`WaveBuilder::extract`] with a normal for every vertex.
+    /// Vertices merged by [`WaveBuilder::bake`] are split again where the faces using them meet at a hard edge,
+    /// so the bank of a river stays sharp even though the water and the bank share vertices
+    pub fn extract_with_normals(&self, mode: NormalMode) -> MeshWithNormals {
+        extract_with_normals(&self.vertexs, &self.indices, mode)
+    }
+
+    /// Like [`WaveBuilder::extract_mesh`] with `Mesh::ATTRIBUTE_NORMAL` filled in
+    #[cfg(feature = "with_bevy")]
+    pub fn extract_mesh_with_normals(
+        &self,
+        topology: bevy::render::render_resource::PrimitiveTopology,
+        mode: NormalMode,
+    ) -> bevy::prelude::Mesh {
+        mesh_with_normals(topology, self.extract_with_normals(mode))
+    }
+}
@@ -0,0 +1,36 @@
+use bevy::{
+    prelude::Mesh,
+    render::{mesh::GenerateTangentsError, render_resource::PrimitiveTopology},
+};
+
+use super::{NormalMode, WaveBuilder, WaveMesh};
+use crate::vertex::{VertexPosition, VertexUV};
+
+fn with_tangents(mut mesh: Mesh) -> Result<Mesh, GenerateTangentsError> {
+    mesh.generate_tangents()?;
+    Ok(mesh)
+}
+
+impl<P: VertexPosition, UV: VertexUV> WaveMesh<P, UV> {
+    /// Like [`WaveMesh::extract_mesh_with_normals`] with `Mesh::ATTRIBUTE_TANGENT` filled in by MikkTSpace
+    /// so normal maps line up with other tools, the tangents follow the UVs given by [`VertexUV::to_f32x2`].
+    /// Tangents only make sense for triangles so the mesh is always a `TriangleList`
+    pub fn extract_mesh_with_tangents(
+        &self,
+        mode: NormalMode,
+    ) -> Result<Mesh, GenerateTangentsError> {
+        with_tangents(self.extract_mesh_with_normals(PrimitiveTopology::TriangleList, mode))
+    }
+}
+
+impl<P: VertexPosition, UV: VertexUV> WaveBuilder<P, UV> {
+    /// Like [`WaveBuilder::extract_mesh_with_normals`] with `Mesh::ATTRIBUTE_TANGENT` filled in by MikkTSpace
+    /// so normal maps line up with other tools, the tangents follow the UVs given by [`VertexUV::to_f32x2`].
+    /// Tangents only make sense for triangles so the mesh is always a `TriangleList`
+    pub fn extract_mesh_with_tangents(
+        &self,
+        mode: NormalMode,
+    ) -> Result<Mesh, GenerateTangentsError> {
+        with_tangents(self.extract_mesh_with_normals(PrimitiveTopology::TriangleList, mode))
+    }
+}